@@ -9,9 +9,10 @@ use clap::{ArgMatches, Args, Command, FromArgMatches, Subcommand};
 use log::info;
 
 use crate::{
+    axon_nodes::profile::Profile,
     constants::{
         APM_CONFIGS, APM_MONITOR_PROMETHEUS_TEMPLATE, APM_MONITOR_VARS_TEMPLATE,
-        DEFAULT_APM_MONITOR_PATH, DEFAULT_APM_PATH,
+        DEFAULT_APM_MONITOR_PATH, DEFAULT_APM_PATH, DEFAULT_PROFILE,
     },
     sub_command::SubCommand,
     types::Result,
@@ -27,6 +28,11 @@ struct StartApmMonitorArgs {
     /// the working path of APM monitor
     #[clap(short, long, default_value=*DEFAULT_APM_MONITOR_PATH)]
     monitor_path: String,
+
+    /// the profile id of the nodes whose collecting ports are scraped, the
+    /// default port 8100 is scraped if the profile is not found
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
 }
 
 #[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
@@ -137,10 +143,29 @@ fn extract_apm_configs(path: impl AsRef<Path>) -> Result<()> {
     Ok(())
 }
 
+/// The scrape targets of Axon's exporters, reachable from the monitor through
+/// the docker bridge
+fn axon_exporter_targets(profile: &str) -> Result<String> {
+    let ports = Profile::load(profile)?
+        .map(|profile| profile.ports)
+        .unwrap_or_default();
+
+    if ports.is_empty() {
+        return Ok("'172.17.0.1:8100'".to_string());
+    }
+
+    Ok(ports
+        .iter()
+        .map(|ports| format!("'172.17.0.1:{}'", ports.collecting))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
 fn start_monitor(args: StartApmMonitorArgs) -> Result<()> {
     let StartApmMonitorArgs {
         path: path_str,
         monitor_path,
+        profile,
     } = args;
     let path: &Path = path_str.as_ref();
 
@@ -153,7 +178,8 @@ fn start_monitor(args: StartApmMonitorArgs) -> Result<()> {
         path.join("apm_monitor_prometheus_template.yaml"),
         APM_MONITOR_PROMETHEUS_TEMPLATE,
     )?
-    .replace("{MONITOR_PATH}", &monitor_path);
+    .replace("{MONITOR_PATH}", &monitor_path)
+    .replace("{AXON_EXPORTER_TARGETS}", &axon_exporter_targets(&profile)?);
 
     write(
         path.join("deploy")
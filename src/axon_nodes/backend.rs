@@ -0,0 +1,145 @@
+use async_trait::async_trait;
+use clap::ArgEnum;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    nodes::{node_start_args, NodeContainer},
+    profile::{NodePorts, Profile},
+};
+use crate::{
+    constants::{
+        LABEL_ADDRESS, LABEL_COLLECTING_PORT, LABEL_HTTP_PORT, LABEL_NODE, LABEL_P2P_PORT,
+        ROLE_NODE,
+    },
+    docker::{label_value, profile_labels, ContainerResources, DockerApi},
+    types::Result,
+};
+
+/// Where the nodes of a profile run
+#[derive(
+    ArgEnum, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// containers of Docker
+    Docker,
+    /// local processes of an axon binary
+    Process,
+}
+
+impl Default for BackendKind {
+    fn default() -> Self {
+        Self::Docker
+    }
+}
+
+/// The lifecycle of nodes, implemented by Docker and local processes
+#[async_trait]
+pub trait NodeBackend: Send + Sync {
+    /// Create what the nodes of a profile share, e.g. the network and volume
+    async fn prepare(&self, profile: &Profile) -> Result<()>;
+
+    /// Start a node, creating it first if it doesn't exist
+    async fn start_node(
+        &self,
+        profile: &Profile,
+        ports: &NodePorts,
+        resources: &ContainerResources,
+    ) -> Result<()>;
+
+    /// List the nodes of a profile, including stopped ones, sorted by their
+    /// indexes
+    async fn list_nodes(&self, profile: &str) -> Result<Vec<NodeContainer>>;
+
+    async fn stop_node(&self, name: &str) -> Result<()>;
+
+    async fn remove_node(&self, name: &str, force: bool) -> Result<()>;
+
+    async fn tail_logs(&self, name: &str, lines: usize) -> Result<Vec<String>>;
+
+    /// Remove the chain data of a profile
+    async fn clean(&self, profile: &Profile) -> Result<()>;
+}
+
+#[async_trait]
+impl NodeBackend for DockerApi {
+    async fn prepare(&self, profile: &Profile) -> Result<()> {
+        let labels = profile_labels(&profile.id, ROLE_NODE);
+
+        self.ensure_network(&profile.network, &labels).await?;
+        self.ensure_volume(&profile.data_volume, &labels).await?;
+
+        Ok(())
+    }
+
+    async fn start_node(
+        &self,
+        profile: &Profile,
+        ports: &NodePorts,
+        resources: &ContainerResources,
+    ) -> Result<()> {
+        Ok(self
+            .start_axon(node_start_args(
+                profile,
+                ports.index,
+                ports.http,
+                ports.p2p,
+                ports.collecting,
+                resources,
+            ))
+            .await?)
+    }
+
+    async fn list_nodes(&self, profile: &str) -> Result<Vec<NodeContainer>> {
+        let containers = self
+            .list_containers(&profile_labels(profile, ROLE_NODE))
+            .await?;
+
+        let mut nodes = containers
+            .into_iter()
+            .filter_map(|container| {
+                let name = container
+                    .names
+                    .and_then(|names| names.into_iter().next())?
+                    .trim_start_matches('/')
+                    .to_string();
+                let labels = container.labels.unwrap_or_default();
+
+                Some(NodeContainer {
+                    name,
+                    index: label_value(&labels, LABEL_NODE)?,
+                    state: container.state.unwrap_or_default(),
+                    http_port: label_value(&labels, LABEL_HTTP_PORT),
+                    p2p_port: label_value(&labels, LABEL_P2P_PORT),
+                    collecting_port: label_value(&labels, LABEL_COLLECTING_PORT),
+                    address: labels.get(LABEL_ADDRESS).cloned(),
+                    resources: ContainerResources::from_labels(&labels),
+                })
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|node| node.index);
+
+        Ok(nodes)
+    }
+
+    async fn stop_node(&self, name: &str) -> Result<()> {
+        Ok(self.stop_one_container(name).await?)
+    }
+
+    async fn remove_node(&self, name: &str, force: bool) -> Result<()> {
+        Ok(self.remove_one_container(name, force).await?)
+    }
+
+    async fn tail_logs(&self, name: &str, lines: usize) -> Result<Vec<String>> {
+        Ok(self.tail_container_logs(name, lines).await?)
+    }
+
+    async fn clean(&self, profile: &Profile) -> Result<()> {
+        Ok(self
+            .remove_labeled_volume(
+                &profile.data_volume,
+                &profile_labels(&profile.id, ROLE_NODE),
+            )
+            .await?)
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    fs::create_dir_all,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Local;
+use clap::{Args, Subcommand};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    nodes::{daemon_nodes, find_nodes_on_daemons, NodeContainer},
+    profile::Profile,
+};
+use crate::{
+    constants::{DEFAULT_CHAOS_PATH, DEFAULT_PROFILE},
+    docker::DockerApi,
+    types::{DockerArgs, Result},
+    utils::{from_json_file, parse_duration, to_json_file},
+};
+
+#[derive(Subcommand, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum ChaosActions {
+    /// Pause the containers of validators
+    Pause(ChaosTargetArgs),
+
+    /// Kill the containers of validators
+    Kill(ChaosTargetArgs),
+
+    /// Disconnect validators from the network of Axon to create a partition
+    Partition(ChaosTargetArgs),
+
+    /// Restart validators once or on a schedule
+    Restart(RestartChaosArgs),
+
+    /// Add network latency to validators with tc
+    Latency(LatencyChaosArgs),
+
+    /// Undo all faults that are still in effect
+    Heal(ChaosProfileArgs),
+
+    /// Show the timeline of chaos actions
+    Timeline(ChaosProfileArgs),
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct ChaosProfileArgs {
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct ChaosTargetArgs {
+    /// indexes of the target nodes seperated by ',', e.g. 1,3
+    #[clap(short = 'i', long, value_delimiter = ',')]
+    nodes: Vec<u32>,
+
+    /// number of target nodes, taken from the highest indexes
+    #[clap(short, long, conflicts_with = "nodes")]
+    count: Option<u32>,
+
+    #[clap(flatten)]
+    profile_args: ChaosProfileArgs,
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct RestartChaosArgs {
+    /// interval between restarts, e.g. 30s, restart only once if not specified
+    #[clap(short, long)]
+    every: Option<String>,
+
+    /// number of restarts when restarting on a schedule
+    #[clap(short, long, default_value = "3")]
+    times: u32,
+
+    #[clap(flatten)]
+    target_args: ChaosTargetArgs,
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct LatencyChaosArgs {
+    /// the delay added to outgoing packets, e.g. 200ms
+    #[clap(long, default_value = "200ms")]
+    delay: String,
+
+    /// the jitter of the delay, e.g. 50ms
+    #[clap(long)]
+    jitter: Option<String>,
+
+    #[clap(flatten)]
+    target_args: ChaosTargetArgs,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum ChaosAction {
+    Pause,
+    Unpause,
+    Kill,
+    Start,
+    Disconnect { network: String },
+    Connect { network: String },
+    AddLatency { delay: String },
+    RemoveLatency,
+    Restart,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+struct ChaosEvent {
+    time:   String,
+    node:   String,
+    #[serde(flatten)]
+    action: ChaosAction,
+    /// whether the fault is still in effect and has to be undone by healing
+    active: bool,
+}
+
+struct Timeline {
+    path:   PathBuf,
+    events: Vec<ChaosEvent>,
+}
+
+impl Timeline {
+    fn load(profile: &str) -> Result<Self> {
+        let path = Path::new(*DEFAULT_CHAOS_PATH).join(format!("{profile}.json"));
+        let events = if path.exists() {
+            from_json_file(&path)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self { path, events })
+    }
+
+    fn save(&self) -> Result<()> {
+        create_dir_all(*DEFAULT_CHAOS_PATH)?;
+
+        to_json_file(&self.events, &self.path)
+    }
+
+    fn record(&mut self, node: &str, action: ChaosAction, active: bool) -> Result<()> {
+        let event = ChaosEvent {
+            time: Local::now().to_rfc3339(),
+            node: node.to_string(),
+            action,
+            active,
+        };
+        info!("[chaos] {} {} {:?}", event.time, event.node, event.action);
+
+        self.events.push(event);
+        self.save()
+    }
+}
+
+fn select_targets<'a>(
+    daemons: &'a [(DockerApi, Vec<NodeContainer>)],
+    nodes: &[u32],
+    count: Option<u32>,
+) -> Result<Vec<(&'a DockerApi, &'a NodeContainer)>> {
+    let all = daemon_nodes(daemons);
+
+    let targets = match count {
+        Some(count) => {
+            let skip = all.len().saturating_sub(usize::try_from(count)?);
+            all.into_iter().skip(skip).collect::<Vec<_>>()
+        }
+        None => all
+            .into_iter()
+            .filter(|(_, node)| nodes.contains(&node.index))
+            .collect(),
+    };
+
+    if targets.is_empty() {
+        return Err("No target node selected, see \"--nodes\" and \"--count\"".into());
+    }
+
+    Ok(targets)
+}
+
+fn latency_command<'a>(delay: &'a str, jitter: Option<&'a str>) -> Vec<&'a str> {
+    let mut cmd = vec![
+        "tc", "qdisc", "replace", "dev", "eth0", "root", "netem", "delay", delay,
+    ];
+    cmd.extend(jitter);
+
+    cmd
+}
+
+const CLEAR_LATENCY_COMMAND: [&str; 6] = ["tc", "qdisc", "del", "dev", "eth0", "root"];
+
+pub async fn exec_chaos(action: ChaosActions) -> Result<()> {
+    match action {
+        ChaosActions::Pause(args) => fault(args, ChaosAction::Pause).await,
+        ChaosActions::Kill(args) => fault(args, ChaosAction::Kill).await,
+        ChaosActions::Partition(args) => {
+            let network = Profile::load_or_default(&args.profile_args.profile)?.network;
+            fault(args, ChaosAction::Disconnect { network }).await
+        }
+        ChaosActions::Latency(LatencyChaosArgs {
+            delay,
+            jitter,
+            target_args,
+        }) => {
+            let delay = match jitter {
+                Some(jitter) => format!("{delay} {jitter}"),
+                None => delay,
+            };
+            fault(target_args, ChaosAction::AddLatency { delay }).await
+        }
+        ChaosActions::Restart(args) => restart(args).await,
+        ChaosActions::Heal(args) => heal(args).await,
+        ChaosActions::Timeline(ChaosProfileArgs { profile, .. }) => {
+            let timeline = Timeline::load(&profile)?;
+
+            for event in timeline.events.iter() {
+                println!(
+                    "{} {} {:?}{}",
+                    event.time,
+                    event.node,
+                    event.action,
+                    if event.active { " (active)" } else { "" }
+                );
+            }
+
+            Ok(())
+        }
+    }
+}
+
+async fn apply(docker_api: &DockerApi, node: &str, action: &ChaosAction) -> Result<()> {
+    match action {
+        ChaosAction::Pause => docker_api.pause_one_container(node).await?,
+        ChaosAction::Unpause => docker_api.unpause_one_container(node).await?,
+        ChaosAction::Kill => docker_api.kill_one_container(node).await?,
+        ChaosAction::Start => docker_api.start_one_container(node).await?,
+        ChaosAction::Restart => docker_api.restart_one_container(node).await?,
+        ChaosAction::Disconnect { network } => docker_api.disconnect_network(network, node).await?,
+        ChaosAction::Connect { network } => docker_api.connect_network(network, node).await?,
+        ChaosAction::AddLatency { delay } => {
+            let mut parts = delay.split(' ');
+            let delay = parts.next().unwrap_or_default();
+            let (exit_code, output) = docker_api
+                .exec(node, &latency_command(delay, parts.next()), true)
+                .await?;
+
+            if exit_code != 0 {
+                return Err(format!(
+                    "Failed to add latency to {node}, exit code: {exit_code}, {}",
+                    output.trim()
+                )
+                .into());
+            }
+        }
+        ChaosAction::RemoveLatency => {
+            let (exit_code, output) = docker_api.exec(node, &CLEAR_LATENCY_COMMAND, true).await?;
+
+            // tc fails if there is no latency to remove
+            if exit_code != 0 {
+                warn!(
+                    "Removing latency of {node}, exit code: {exit_code}, {}",
+                    output.trim()
+                );
+            }
+        }
+    };
+
+    Ok(())
+}
+
+/// The action that undoes a fault, if it needs to be undone
+fn undo_action(action: &ChaosAction) -> Option<ChaosAction> {
+    match action {
+        ChaosAction::Pause => Some(ChaosAction::Unpause),
+        ChaosAction::Kill => Some(ChaosAction::Start),
+        ChaosAction::Disconnect { network } => Some(ChaosAction::Connect {
+            network: network.clone(),
+        }),
+        ChaosAction::AddLatency { .. } => Some(ChaosAction::RemoveLatency),
+        _ => None,
+    }
+}
+
+async fn fault(args: ChaosTargetArgs, action: ChaosAction) -> Result<()> {
+    let ChaosTargetArgs {
+        nodes,
+        count,
+        profile_args: ChaosProfileArgs {
+            profile,
+            docker_args,
+        },
+    } = args;
+
+    let daemons = find_nodes_on_daemons(&profile, &docker_args).await?;
+    let mut timeline = Timeline::load(&profile)?;
+
+    for (docker_api, node) in select_targets(&daemons, &nodes, count)? {
+        apply(docker_api, &node.name, &action).await?;
+        timeline.record(&node.name, action.clone(), undo_action(&action).is_some())?;
+    }
+
+    Ok(())
+}
+
+async fn restart(args: RestartChaosArgs) -> Result<()> {
+    let RestartChaosArgs {
+        every,
+        times,
+        target_args:
+            ChaosTargetArgs {
+                nodes,
+                count,
+                profile_args:
+                    ChaosProfileArgs {
+                        profile,
+                        docker_args,
+                    },
+            },
+    } = args;
+
+    let daemons = find_nodes_on_daemons(&profile, &docker_args).await?;
+    let mut timeline = Timeline::load(&profile)?;
+    let targets = select_targets(&daemons, &nodes, count)?;
+    let (interval, times) = match every {
+        Some(every) => (parse_duration(&every)?, times),
+        None => (Duration::ZERO, 1),
+    };
+
+    for round in 0..times {
+        if round > 0 {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = tokio::signal::ctrl_c() => {
+                    info!("Scheduled restarts cancelled after {round} round(s)");
+                    return Ok(());
+                }
+            }
+        }
+
+        for (docker_api, node) in targets.iter() {
+            apply(docker_api, &node.name, &ChaosAction::Restart).await?;
+            timeline.record(&node.name, ChaosAction::Restart, false)?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn heal(args: ChaosProfileArgs) -> Result<()> {
+    let ChaosProfileArgs {
+        profile,
+        docker_args,
+    } = args;
+
+    let daemons = find_nodes_on_daemons(&profile, &docker_args).await?;
+    let nodes = daemon_nodes(&daemons);
+    let mut timeline = Timeline::load(&profile)?;
+
+    let active = timeline
+        .events
+        .iter()
+        .enumerate()
+        .filter(|(_, event)| event.active)
+        .map(|(i, event)| (i, event.node.clone(), undo_action(&event.action)))
+        .collect::<Vec<_>>();
+
+    if active.is_empty() {
+        info!("No active fault of profile {profile}");
+        return Ok(());
+    }
+
+    // undo the latest fault first, e.g. unpause before reconnecting
+    for (i, node, undo) in active.into_iter().rev() {
+        if let Some(undo) = undo {
+            let docker_api = match nodes.iter().find(|(_, container)| container.name == node) {
+                Some((docker_api, _)) => docker_api,
+                None => {
+                    error!("Failed to heal {node} with {undo:?}: container not found");
+                    continue;
+                }
+            };
+            if let Err(err) = apply(docker_api, &node, &undo).await {
+                error!("Failed to heal {node} with {undo:?}: {err}");
+                continue;
+            }
+            timeline.events[i].active = false;
+            timeline.record(&node, undo, false)?;
+        }
+    }
+
+    Ok(())
+}
@@ -3,12 +3,9 @@ use std::{
     path::Path,
 };
 
-use axon_protocol::{
-    codec::hex_decode,
-    types::{
-        Address, Eip1559Transaction, Hasher, Hex, SignedTransaction, TransactionAction,
-        UnsignedTransaction, UnverifiedTransaction, ValidatorExtend, H160, U256,
-    },
+use axon_protocol::types::{
+    Eip1559Transaction, Hasher, Hex, SignedTransaction, TransactionAction, UnsignedTransaction,
+    UnverifiedTransaction, ValidatorExtend, H160, U256,
 };
 use clap::Args;
 use ethers_core::abi::{Contract, Token};
@@ -20,16 +17,18 @@ use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
 use tentacle_secio::SecioKeyPair;
 
+use super::profile::Profile;
 use crate::{
     constants::{
         CONFIG_TEMPLATE, CROSS_CHAIN_ABI, CROSS_CHAIN_CONTRACT, DB_OPTION_TEMPLATE,
-        DEFAULT_NODES_PATH, DEFAULT_NODE_KEY_PAIRS_PATH, GENESIS_TEMPLATE, METADATA_ABI,
-        METADATA_CONTRACT, METADATA_TEMPLATE, PROXY_ABI, PROXY_CONTRACT, TOKEN_ABI, TOKEN_CONTRACT,
-        VALIDATOR_TEMPLATE,
+        DEFAULT_NODES_PATH, DEFAULT_NODE_KEY_PAIRS_PATH, DEFAULT_PROFILE, DOCKER_BRIDGE_ADDRESS,
+        GENESIS_TEMPLATE, METADATA_ABI, METADATA_CONTRACT, METADATA_TEMPLATE, PROXY_ABI,
+        PROXY_CONTRACT, TOKEN_ABI, TOKEN_CONTRACT, VALIDATOR_TEMPLATE,
     },
     types::Result,
     utils::{
-        from_json_file, read_or_create_json_template, read_or_create_plain_template, to_json_file,
+        from_json_file, hex_decode, pubkey_address, read_or_create_json_template,
+        read_or_create_plain_template, to_json_file,
     },
 };
 
@@ -61,6 +60,11 @@ pub struct ConfigGenArgs {
     /// the p2p address of nodes
     #[clap(short, long, value_delimiter = ',')]
     addresses: Vec<String>,
+
+    /// the profile whose hosts and p2p ports are used for the nodes without
+    /// an address
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone)]
@@ -96,7 +100,7 @@ fn get_key_pair_from_private_key(
     let peer_id = secp256k1_public_key.peer_id().to_base58();
     let secp256k1_public_key_raw = secp256k1_public_key.inner();
 
-    let address = Address::from_pubkey_bytes(&secp256k1_public_key_raw)?;
+    let address = pubkey_address(&secp256k1_public_key_raw)?;
 
     Ok(KeyPair {
         bls_private_key: Hex::encode(bls_private_key_raw),
@@ -173,7 +177,7 @@ fn get_tx(
     })
 }
 
-fn sign_tx(
+pub fn sign_tx(
     private_key: &Secp256k1RecoverablePrivateKey,
     tx: UnsignedTransaction,
     chain_id: u64,
@@ -195,7 +199,7 @@ fn sign_tx(
     utx.try_into().unwrap()
 }
 
-fn contract_address(address: &H160, nonce: u32) -> H160 {
+pub fn contract_address(address: &H160, nonce: u32) -> H160 {
     contract_address::ContractAddress::from_sender_and_nonce(
         &address.as_fixed_bytes().into(),
         &nonce.into(),
@@ -209,6 +213,7 @@ pub fn generate_configs(args: &ConfigGenArgs) -> Result<()> {
         key_pairs_path,
         path: path_str,
         addresses,
+        profile,
     } = args;
 
     let path: &Path = path_str.as_ref();
@@ -406,15 +411,40 @@ pub fn generate_configs(args: &ConfigGenArgs) -> Result<()> {
     to_json_file(&genesis, path.join("genesis.json"))?;
     info!("Genesis file generated");
 
-    let bootstraps = key_pairs.iter().enumerate().map(|(i, key_pair)| {
+    let profile = Profile::load_or_default(profile)?;
+    let mut bootstraps = Vec::new();
+    for (i, key_pair) in key_pairs.iter().enumerate() {
         let peer_id = &key_pair.peer_id;
+        let index = u32::try_from(i + 1)?;
 
-        if i < addresses.len() {
-            format!("[[network.bootstraps]]\nmulti_address = \"{}/p2p/{peer_id}\"", &addresses[i])
+        let multi_address = if i < addresses.len() {
+            format!("{}/p2p/{peer_id}", &addresses[i])
         } else {
-            format!("[[network.bootstraps]]\nmulti_address = \"/ip4/172.17.0.1/tcp/{}/p2p/{peer_id}\"", 10000 + i)
-        }
-    }).reduce(|a, b| format!("{a}\n{b}")).unwrap_or_else(|| "".to_string());
+            // the bridge is only reachable by nodes on the same daemon
+            let address = match profile.host(index) {
+                Some(host) => host.address.as_str(),
+                None if profile.hosts.is_empty() => DOCKER_BRIDGE_ADDRESS,
+                None => {
+                    return Err(format!(
+                        "axon{index} has no host while other nodes of profile {} do,                          see \"axon host add\"",
+                        profile.id
+                    )
+                    .into())
+                }
+            };
+            let port = profile
+                .ports
+                .iter()
+                .find(|ports| ports.index == index)
+                .map_or(10000 + index - 1, |ports| ports.p2p);
+
+            format!("/ip4/{address}/tcp/{port}/p2p/{peer_id}")
+        };
+        bootstraps.push(format!(
+            "[[network.bootstraps]]\nmulti_address = \"{multi_address}\""
+        ));
+    }
+    let bootstraps = bootstraps.join("\n");
 
     key_pairs
         .iter()
@@ -0,0 +1,273 @@
+use clap::{Args, Subcommand};
+use colored::*;
+use log::{error, info};
+use nix::{
+    libc::{self, STDIN_FILENO, STDOUT_FILENO},
+    sys::termios::{cfmakeraw, tcgetattr, tcsetattr, SetArg, Termios},
+};
+
+use super::nodes::{daemon_nodes, find_nodes_on_daemons, NodeContainer};
+use crate::{
+    constants::DEFAULT_PROFILE,
+    docker::DockerApi,
+    types::{DockerArgs, Result},
+};
+
+/// The p2p port inside the containers of nodes
+const P2P_PORT: u32 = 8001;
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct NodeArgs {
+    /// the index of the node, e.g. 1 for axon1
+    #[clap(short = 'i', long, default_value = "1")]
+    node: u32,
+
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct ExecArgs {
+    #[clap(flatten)]
+    node_args: NodeArgs,
+
+    /// the command to run in the container, after "--"
+    #[clap(last = true, required = true)]
+    cmd: Vec<String>,
+}
+
+#[derive(Subcommand, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum NetActions {
+    /// Check the TCP reachability of the p2p port between every pair of nodes
+    Check(NetCheckArgs),
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct NetCheckArgs {
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// seconds to wait for each connection
+    #[clap(short, long, default_value = "3")]
+    timeout: u64,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+/// Find a node of a profile and the Docker daemon it runs on
+async fn find_node(
+    profile: &str,
+    index: u32,
+    docker_args: &DockerArgs,
+) -> Result<(DockerApi, NodeContainer)> {
+    find_nodes_on_daemons(profile, docker_args)
+        .await?
+        .into_iter()
+        .find_map(|(docker_api, nodes)| {
+            let node = nodes.into_iter().find(|node| node.index == index)?;
+            Some((docker_api, node))
+        })
+        .ok_or_else(|| format!("Node {index} of profile {profile} not found").into())
+}
+
+pub async fn exec_node(args: ExecArgs) -> Result<()> {
+    let ExecArgs {
+        node_args:
+            NodeArgs {
+                node,
+                profile,
+                docker_args,
+            },
+        cmd,
+    } = args;
+
+    let (docker_api, node) = find_node(&profile, node, &docker_args).await?;
+
+    let (exit_code, output) = docker_api.exec(&node.name, &cmd, false).await?;
+    print!("{output}");
+
+    if exit_code != 0 {
+        return Err(format!("{} exited with code {exit_code}", cmd.join(" ")).into());
+    }
+
+    Ok(())
+}
+
+/// The terminal in raw mode, so that keys reach the shell in the container as
+/// they are typed, restored when dropped
+struct RawTerminal(Termios);
+
+impl RawTerminal {
+    fn new() -> Result<Self> {
+        let original = tcgetattr(STDIN_FILENO)?;
+        let mut raw = original.clone();
+        cfmakeraw(&mut raw);
+        tcsetattr(STDIN_FILENO, SetArg::TCSANOW, &raw)?;
+
+        Ok(Self(original))
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = tcsetattr(STDIN_FILENO, SetArg::TCSANOW, &self.0);
+    }
+}
+
+/// The rows and columns of the terminal
+fn terminal_size() -> Option<(u16, u16)> {
+    let mut size = libc::winsize {
+        ws_row:    0,
+        ws_col:    0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes the winsize
+    let result = unsafe { libc::ioctl(STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+
+    (result == 0 && size.ws_row > 0).then_some((size.ws_row, size.ws_col))
+}
+
+/// An interactive shell with a TTY, bash if the image has it
+pub async fn open_shell(args: NodeArgs) -> Result<()> {
+    let NodeArgs {
+        node,
+        profile,
+        docker_args,
+    } = args;
+
+    let (docker_api, node) = find_node(&profile, node, &docker_args).await?;
+    info!(
+        "Running a shell in {}, type \"exit\" or press CTRL-D to quit",
+        node.name
+    );
+
+    let size = terminal_size();
+    let exit_code = {
+        let _terminal = RawTerminal::new()?;
+
+        docker_api
+            .exec_tty(
+                &node.name,
+                &[
+                    "sh",
+                    "-c",
+                    "command -v bash >/dev/null && exec bash || exec sh",
+                ],
+                size,
+            )
+            .await?
+    };
+
+    if exit_code != 0 {
+        return Err(format!("The shell exited with code {exit_code}").into());
+    }
+
+    Ok(())
+}
+
+pub async fn exec_net(action: NetActions) -> Result<()> {
+    match action {
+        NetActions::Check(args) => check_net(args).await,
+    }
+}
+
+/// The address and port `from` reaches the p2p port of `to` by, the name of
+/// the container on the same host and the published port on another one
+fn peer_endpoint(from: &NodeContainer, to: &NodeContainer) -> Result<(String, u32)> {
+    if from.address == to.address {
+        return Ok((to.name.clone(), P2P_PORT));
+    }
+
+    match (&to.address, to.p2p_port) {
+        (Some(address), Some(port)) => Ok((address.clone(), port)),
+        _ => Err(format!("{} has no address reachable from other hosts", to.name).into()),
+    }
+}
+
+/// Probe the p2p port of `to` from inside `from` with nc, or bash's
+/// `/dev/tcp` if the image has no nc
+async fn probe(
+    docker_api: &DockerApi,
+    from: &NodeContainer,
+    to: &NodeContainer,
+    timeout: u64,
+) -> Result<()> {
+    let (host, port) = peer_endpoint(from, to)?;
+    let script = format!(
+        "if command -v nc >/dev/null; then nc -z -w {timeout} {host} {port}; \
+         elif ! command -v bash >/dev/null; then exit 127; \
+         elif command -v timeout >/dev/null; then \
+         timeout {timeout} bash -c '</dev/tcp/{host}/{port}'; \
+         else bash -c '</dev/tcp/{host}/{port}'; fi"
+    );
+    let (exit_code, output) = docker_api
+        .exec(&from.name, &["sh", "-c", &script], false)
+        .await?;
+
+    match exit_code {
+        0 => Ok(()),
+        127 => Err(format!("{} has neither nc nor bash to probe with", from.name).into()),
+        _ if output.trim().is_empty() => Err(format!("{host}:{port} is unreachable").into()),
+        _ => Err(format!("{host}:{port} is unreachable, {}", output.trim()).into()),
+    }
+}
+
+async fn check_net(args: NetCheckArgs) -> Result<()> {
+    let NetCheckArgs {
+        profile,
+        timeout,
+        docker_args,
+    } = args;
+
+    let daemons = find_nodes_on_daemons(&profile, &docker_args).await?;
+    let nodes = daemon_nodes(&daemons);
+    if nodes.len() < 2 {
+        info!("At least 2 nodes are needed to check the network");
+        return Ok(());
+    }
+
+    // probe from inside `from`, on the daemon it runs on
+    let pairs = nodes
+        .iter()
+        .flat_map(|(docker_api, from)| {
+            nodes
+                .iter()
+                .filter(move |(_, to)| to.index != from.index)
+                .map(move |(_, to)| (*docker_api, *from, *to))
+        })
+        .collect::<Vec<_>>();
+
+    let results = futures::future::join_all(
+        pairs
+            .iter()
+            .map(|(docker_api, from, to)| probe(docker_api, from, to, timeout)),
+    )
+    .await;
+
+    let mut failed = 0;
+    for ((_, from, to), result) in pairs.iter().zip(results) {
+        let (from, to) = (&from.name, &to.name);
+        match result {
+            Ok(()) => info!("{} {} -> {}", "✔".green(), from, to),
+            Err(err) => {
+                failed += 1;
+                error!("{} {} -> {}: {}", "✘".red(), from, to, err);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{failed} of {} connection(s) failed", pairs.len()).into());
+    }
+
+    info!("All {} connection(s) are reachable", pairs.len());
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::time::{Duration, Instant};
+
+use colored::*;
+use log::{error, info, warn};
+
+use super::backend::NodeBackend;
+use crate::{
+    constants::{HEALTH_CHECK_INTERVAL, HEALTH_CHECK_LOG_LINES},
+    rpc::{check_metrics, RpcClient},
+    types::Result,
+};
+
+#[derive(Debug, Clone)]
+pub struct NodeEndpoints {
+    pub name:        String,
+    pub http_url:    String,
+    pub metrics_url: String,
+}
+
+#[derive(Debug, Default)]
+pub struct NodeReadiness {
+    pub name:          String,
+    pub chain_id:      Option<u64>,
+    pub height:        Option<u64>,
+    pub metrics_ready: bool,
+    pub last_error:    Option<String>,
+}
+
+impl NodeReadiness {
+    pub fn is_ready(&self) -> bool {
+        self.chain_id.is_some() && self.height.is_some() && self.metrics_ready
+    }
+}
+
+async fn check_node(endpoints: &NodeEndpoints, readiness: &mut NodeReadiness) -> Result<()> {
+    let rpc = RpcClient::new(&endpoints.http_url)?;
+
+    if readiness.chain_id.is_none() {
+        readiness.chain_id = Some(rpc.chain_id().await?);
+    }
+    if readiness.height.is_none() {
+        readiness.height = Some(rpc.block_number().await?);
+    }
+    if !readiness.metrics_ready {
+        check_metrics(&endpoints.metrics_url).await?;
+        readiness.metrics_ready = true;
+    }
+
+    Ok(())
+}
+
+/// Poll the JSON-RPC and Prometheus endpoints of a node until both respond or
+/// the timeout expires
+pub async fn wait_for_node(endpoints: &NodeEndpoints, timeout: Duration) -> NodeReadiness {
+    let deadline = Instant::now() + timeout;
+    let mut readiness = NodeReadiness {
+        name: endpoints.name.clone(),
+        ..Default::default()
+    };
+
+    loop {
+        match check_node(endpoints, &mut readiness).await {
+            Ok(()) => return readiness,
+            Err(err) => readiness.last_error = Some(err.to_string()),
+        }
+
+        if Instant::now() >= deadline {
+            return readiness;
+        }
+
+        tokio::time::sleep(Duration::from_secs(HEALTH_CHECK_INTERVAL)).await;
+    }
+}
+
+/// Wait for all nodes to be ready, report the result of each node and dump the
+/// last log lines of the failed ones
+pub async fn wait_for_nodes(
+    backend: &dyn NodeBackend,
+    nodes: &[NodeEndpoints],
+    timeout: Duration,
+) -> Result<Vec<NodeReadiness>> {
+    info!(
+        "Waiting up to {}s for {} node(s) to be ready...",
+        timeout.as_secs(),
+        nodes.len()
+    );
+
+    let results =
+        futures::future::join_all(nodes.iter().map(|node| wait_for_node(node, timeout))).await;
+
+    for readiness in results.iter() {
+        if readiness.is_ready() {
+            info!(
+                "{} {} is ready, chain id: {}, block height: {}",
+                "✔".green(),
+                readiness.name,
+                readiness.chain_id.unwrap_or_default(),
+                readiness.height.unwrap_or_default(),
+            );
+            continue;
+        }
+
+        error!(
+            "{} {} is not ready after {}s, rpc: {}, metrics: {}, last error: {}",
+            "✘".red(),
+            readiness.name,
+            timeout.as_secs(),
+            if readiness.height.is_some() {
+                "up"
+            } else {
+                "down"
+            },
+            if readiness.metrics_ready {
+                "up"
+            } else {
+                "down"
+            },
+            readiness.last_error.as_deref().unwrap_or(""),
+        );
+
+        match backend
+            .tail_logs(&readiness.name, HEALTH_CHECK_LOG_LINES)
+            .await
+        {
+            Ok(lines) => {
+                warn!("Last {} log lines of {}:", lines.len(), readiness.name);
+                lines.iter().for_each(|line| println!("{}", line));
+            }
+            Err(err) => error!("Failed to read logs of {}: {}", readiness.name, err),
+        }
+    }
+
+    Ok(results)
+}
@@ -0,0 +1,118 @@
+use clap::{Args, Subcommand};
+use log::info;
+
+use super::profile::{NodeHost, Profile};
+use crate::{
+    constants::{DEFAULT_PROFILE, DOCKER_BRIDGE_ADDRESS},
+    docker::uri_host,
+    types::Result,
+};
+
+#[derive(Subcommand, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum HostActions {
+    /// Run a node on another Docker daemon, the config files have to be at
+    /// the same path on that host
+    Add(AddHostArgs),
+
+    /// Run a node on the default Docker daemon again
+    Rm(RmHostArgs),
+
+    /// List the hosts of nodes
+    Ls(HostProfileArgs),
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct HostProfileArgs {
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct AddHostArgs {
+    /// the index of the node, e.g. 2 for axon2
+    #[clap(short = 'i', long)]
+    node: u32,
+
+    /// uri of the docker service on the host, e.g. tcp://10.0.0.2:2376 or
+    /// ssh://user@10.0.0.2
+    #[clap(short = 'u', long)]
+    docker_uri: String,
+
+    /// the directory of ca.pem, cert.pem and key.pem to connect with TLS
+    #[clap(long)]
+    tls_cert_path: Option<String>,
+
+    /// the address other nodes and this machine reach the host by, defaults
+    /// to the host of the uri
+    #[clap(short, long)]
+    address: Option<String>,
+
+    #[clap(flatten)]
+    profile_args: HostProfileArgs,
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct RmHostArgs {
+    /// the index of the node, e.g. 2 for axon2
+    #[clap(short = 'i', long)]
+    node: u32,
+
+    #[clap(flatten)]
+    profile_args: HostProfileArgs,
+}
+
+pub fn exec_host(action: HostActions) -> Result<()> {
+    match action {
+        HostActions::Add(AddHostArgs {
+            node,
+            docker_uri,
+            address,
+            tls_cert_path,
+            profile_args: HostProfileArgs { profile },
+        }) => {
+            let mut profile = Profile::load_or_default(&profile)?;
+            let address = address.unwrap_or_else(|| {
+                uri_host(&docker_uri)
+                    .unwrap_or(DOCKER_BRIDGE_ADDRESS)
+                    .to_string()
+            });
+
+            profile.hosts.retain(|host| host.index != node);
+            profile.hosts.push(NodeHost {
+                index: node,
+                docker_uri,
+                address,
+                tls_cert_path,
+            });
+            profile.hosts.sort_by_key(|host| host.index);
+            profile.save()?;
+
+            info!("Host of axon{node} saved, regenerate config files with \"axon config-gen --profile {}\" to update bootstraps", profile.id);
+        }
+        HostActions::Rm(RmHostArgs {
+            node,
+            profile_args: HostProfileArgs { profile },
+        }) => {
+            let mut profile = Profile::load_or_default(&profile)?;
+
+            profile.hosts.retain(|host| host.index != node);
+            profile.save()?;
+        }
+        HostActions::Ls(HostProfileArgs { profile }) => {
+            let profile = Profile::load_or_default(&profile)?;
+
+            if profile.hosts.is_empty() {
+                info!(
+                    "All nodes of profile {} run on the default host",
+                    profile.id
+                );
+            }
+            for host in profile.hosts.iter() {
+                println!("axon{} {} {}", host.index, host.docker_uri, host.address);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use clap::{Args, Subcommand};
+use log::info;
+
+use crate::{
+    constants::{LOCAL_AXON_IMAGE_NAME, LOCAL_AXON_IMAGE_TAG},
+    docker::DockerApi,
+    types::{DockerArgs, Result},
+};
+
+#[derive(Subcommand, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum ImageActions {
+    /// Build an image of Axon from a local source tree
+    Build(BuildImageArgs),
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct BuildImageArgs {
+    /// the path of the Axon checkout to build
+    #[clap(short, long)]
+    src: String,
+
+    /// the path of the Dockerfile relative to the source tree
+    #[clap(short = 'f', long, default_value = "Dockerfile")]
+    dockerfile: String,
+
+    /// the name of the built image
+    #[clap(short, long, default_value = LOCAL_AXON_IMAGE_NAME)]
+    name: String,
+
+    /// the tag of the built image
+    #[clap(short, long, default_value = LOCAL_AXON_IMAGE_TAG)]
+    tag: String,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+pub async fn build_image(args: BuildImageArgs) -> Result<()> {
+    let BuildImageArgs {
+        src,
+        dockerfile,
+        name,
+        tag,
+        docker_args,
+    } = args;
+
+    if !DockerApi::new(docker_args)
+        .await?
+        .build_image(&src, &dockerfile, &name, &tag)
+        .await?
+    {
+        return Err(format!("Failed to build image {name}:{tag} from {src}").into());
+    }
+
+    info!("Image {name}:{tag} built, see \"axon start --image {name} --tag {tag}\"");
+
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use clap::Args;
+use colored::*;
+use log::info;
+
+use super::nodes::{daemon_nodes, find_nodes_on_daemons};
+use crate::{
+    constants::DEFAULT_PROFILE,
+    docker::DockerApi,
+    types::{DockerArgs, Result},
+    utils::parse_duration,
+};
+
+const PREFIX_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Blue,
+    Color::Red,
+];
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct LogsArgs {
+    /// the index of the node to show logs of, e.g. 1 for axon1
+    #[clap(short = 'i', long, conflicts_with = "all")]
+    node: Option<u32>,
+
+    /// show logs of all nodes, this is the default if no node is specified
+    #[clap(short, long)]
+    all: bool,
+
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// keep streaming new log lines
+    #[clap(short, long, conflicts_with = "output")]
+    follow: bool,
+
+    /// only show logs since a duration ago (e.g. 30s, 10m, 2h) or an RFC 3339
+    /// timestamp
+    #[clap(short, long)]
+    since: Option<String>,
+
+    /// only show lines containing this pattern
+    #[clap(short, long)]
+    grep: Option<String>,
+
+    /// export the logs of the selected nodes merged by timestamp into this
+    /// file
+    #[clap(short, long)]
+    output: Option<String>,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+struct LogLine {
+    timestamp: Option<DateTime<FixedOffset>>,
+    node:      String,
+    message:   String,
+}
+
+/// Split the RFC 3339 timestamp added by Docker from a log line
+pub fn parse_log_line(line: &str) -> (Option<DateTime<FixedOffset>>, &str) {
+    match line.split_once(' ') {
+        Some((timestamp, message)) => match DateTime::parse_from_rfc3339(timestamp) {
+            Ok(timestamp) => (Some(timestamp), message),
+            Err(_) => (None, line),
+        },
+        None => (None, line),
+    }
+}
+
+fn parse_since(since: &str) -> Result<DateTime<FixedOffset>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(since) {
+        return Ok(timestamp);
+    }
+
+    let duration = chrono::Duration::from_std(parse_duration(since)?)?;
+
+    Ok((Utc::now() - duration).into())
+}
+
+/// The daemon filters by `since` at the precision of a second, so the lines are
+/// filtered again by the exact time
+fn is_selected(
+    timestamp: Option<DateTime<FixedOffset>>,
+    message: &str,
+    since: Option<DateTime<FixedOffset>>,
+    grep: Option<&str>,
+) -> bool {
+    if let (Some(since), Some(timestamp)) = (since, timestamp) {
+        if timestamp < since {
+            return false;
+        }
+    }
+
+    grep.map_or(true, |pattern| message.contains(pattern))
+}
+
+pub async fn show_logs(args: LogsArgs) -> Result<()> {
+    let LogsArgs {
+        node,
+        profile,
+        follow,
+        since,
+        grep,
+        output,
+        docker_args,
+        ..
+    } = args;
+
+    let daemons = find_nodes_on_daemons(&profile, &docker_args).await?;
+    let nodes = daemon_nodes(&daemons)
+        .into_iter()
+        .filter(|(_, container)| node.map_or(true, |index| container.index == index))
+        .map(|(docker_api, container)| (docker_api, container.name.as_str()))
+        .collect::<Vec<_>>();
+    if nodes.is_empty() {
+        return Ok(());
+    }
+    let since = since.as_deref().map(parse_since).transpose()?;
+    let grep = grep.as_deref();
+
+    if let Some(output) = output {
+        return export_logs(&nodes, since, grep, output).await;
+    }
+
+    let width = nodes
+        .iter()
+        .map(|(_, name)| name.len())
+        .max()
+        .unwrap_or_default();
+
+    futures::future::join_all(nodes.iter().enumerate().map(|(i, (docker_api, name))| {
+        let prefix = format!("{name:width$} |").color(PREFIX_COLORS[i % PREFIX_COLORS.len()]);
+
+        docker_api.stream_container_logs(name, since.map(Into::into), follow, move |line| {
+            let (timestamp, message) = parse_log_line(&line);
+
+            if is_selected(timestamp, message, since, grep) {
+                println!("{} {}", prefix, message);
+            }
+        })
+    }))
+    .await
+    .into_iter()
+    .collect::<std::result::Result<(), _>>()?;
+
+    Ok(())
+}
+
+async fn export_logs(
+    nodes: &[(&DockerApi, &str)],
+    since: Option<DateTime<FixedOffset>>,
+    grep: Option<&str>,
+    output: String,
+) -> Result<()> {
+    let mut lines = Vec::new();
+
+    for (docker_api, name) in nodes {
+        docker_api
+            .stream_container_logs(name, since.map(Into::into), false, |line| {
+                let (timestamp, message) = parse_log_line(&line);
+
+                if is_selected(timestamp, message, since, grep) {
+                    lines.push(LogLine {
+                        timestamp,
+                        node: name.to_string(),
+                        message: message.to_string(),
+                    });
+                }
+            })
+            .await?;
+    }
+
+    // the sort is stable, so lines of the same node keep their order
+    lines.sort_by_key(|line| line.timestamp);
+
+    let mut writer = BufWriter::new(File::create(&output)?);
+    for LogLine {
+        timestamp,
+        node,
+        message,
+    } in lines.iter()
+    {
+        let timestamp = timestamp.map(|t| t.to_rfc3339()).unwrap_or_default();
+        writeln!(writer, "{timestamp} {node} | {message}")?;
+    }
+    writer.flush()?;
+
+    info!(
+        "Exported {} log lines of {} node(s) to {}",
+        lines.len(),
+        names.len(),
+        output
+    );
+
+    Ok(())
+}
@@ -1,19 +1,38 @@
-mod config;
+mod backend;
+mod chaos;
+pub mod config;
+mod exec;
+mod health;
+mod host;
+mod image;
+pub mod logs;
 mod nodes;
+mod process;
+pub mod profile;
+mod snapshot;
+mod status;
+mod upgrade;
 
 use async_trait::async_trait;
 use clap::{ArgMatches, Command, FromArgMatches, Subcommand};
 
 use self::{
+    chaos::{exec_chaos, ChaosActions},
     config::{generate_configs, generate_key_pairs, log_key_pairs, ConfigGenArgs, KeygenArgs},
+    exec::{exec_net, exec_node, open_shell, ExecArgs, NetActions, NodeArgs},
+    host::{exec_host, HostActions},
+    image::{build_image, ImageActions},
+    logs::{show_logs, LogsArgs},
     nodes::{
-        ps_nodes, rm_nodes, start_nodes, stop_nodes, OperateNodeContainersArgs,
+        clean_nodes, ps_nodes, rm_nodes, start_nodes, stop_nodes, OperateNodeContainersArgs,
         RmNodeContainersArgs, StartNodesArgs,
     },
+    snapshot::{exec_snapshot, SnapshotActions},
+    status::{show_status, StatusArgs},
+    upgrade::{upgrade_nodes, UpgradeArgs},
 };
 use crate::{
-    constants::{DEFAULT_AXON_DATA_VOLUME, DEFAULT_NODE_KEY_PAIRS_PATH},
-    docker::DockerApi,
+    constants::{DEFAULT_NODE_KEY_PAIRS_PATH, DEFAULT_PROFILE},
     sub_command::SubCommand,
     types::{DockerArgs, Result},
 };
@@ -35,11 +54,39 @@ enum AxonNodesActions {
     /// Inspect containers of Axon
     Ps(OperateNodeContainersArgs),
 
+    /// Show or export logs of Axon nodes
+    Logs(LogsArgs),
+
+    /// Show a refreshing dashboard of Axon nodes' status
+    Status(StatusArgs),
+
+    /// Upgrade Axon nodes to a new image one by one
+    Upgrade(UpgradeArgs),
+
+    /// Run a command in the container of a node
+    Exec(ExecArgs),
+
+    /// Run shell commands in the container of a node line by line
+    Shell(NodeArgs),
+
+    /// Manage the Docker hosts nodes run on
+    #[clap(subcommand)]
+    Host(HostActions),
+
+    /// Check the network between Axon nodes
+    #[clap(subcommand)]
+    Net(NetActions),
+
     /// Clean chain data
     Clean {
-        /// the volume of Axon's data
-        #[clap(short='D', long="data", default_value=DEFAULT_AXON_DATA_VOLUME)]
-        data_volume: String,
+        /// the profile id of the nodes
+        #[clap(long, default_value = DEFAULT_PROFILE)]
+        profile: String,
+
+        /// the volume of Axon's data, defaults to the one recorded in the
+        /// profile
+        #[clap(short = 'D', long = "data")]
+        data_volume: Option<String>,
 
         #[clap(flatten)]
         docker_args: DockerArgs,
@@ -57,6 +104,18 @@ enum AxonNodesActions {
 
     /// Generate config files for Axon nodes
     ConfigGen(ConfigGenArgs),
+
+    /// Manage images of Axon
+    #[clap(subcommand)]
+    Image(ImageActions),
+
+    /// Inject faults into Axon nodes
+    #[clap(subcommand)]
+    Chaos(ChaosActions),
+
+    /// Create or restore snapshots of the chain data
+    #[clap(subcommand)]
+    Snapshot(SnapshotActions),
 }
 
 #[async_trait]
@@ -79,13 +138,33 @@ impl SubCommand for AxonNodes {
             AxonNodesActions::Ps(args) => {
                 ps_nodes(args).await?;
             }
+            AxonNodesActions::Logs(args) => {
+                show_logs(args).await?;
+            }
+            AxonNodesActions::Status(args) => {
+                show_status(args).await?;
+            }
+            AxonNodesActions::Upgrade(args) => {
+                upgrade_nodes(args).await?;
+            }
+            AxonNodesActions::Exec(args) => {
+                exec_node(args).await?;
+            }
+            AxonNodesActions::Shell(args) => {
+                open_shell(args).await?;
+            }
+            AxonNodesActions::Host(action) => {
+                exec_host(action)?;
+            }
+            AxonNodesActions::Net(action) => {
+                exec_net(action).await?;
+            }
             AxonNodesActions::Clean {
+                profile,
                 data_volume,
-                docker_args: DockerArgs { docker_uri },
+                docker_args,
             } => {
-                DockerApi::new(docker_uri)?
-                    .remove_one_volume(data_volume)
-                    .await?;
+                clean_nodes(&profile, data_volume, &docker_args).await?;
             }
             AxonNodesActions::Keygen(args) => {
                 generate_key_pairs(&args)?;
@@ -96,6 +175,15 @@ impl SubCommand for AxonNodes {
             AxonNodesActions::ConfigGen(args) => {
                 generate_configs(&args)?;
             }
+            AxonNodesActions::Image(ImageActions::Build(args)) => {
+                build_image(args).await?;
+            }
+            AxonNodesActions::Chaos(action) => {
+                exec_chaos(action).await?;
+            }
+            AxonNodesActions::Snapshot(action) => {
+                exec_snapshot(action).await?;
+            }
         }
 
         Ok(())
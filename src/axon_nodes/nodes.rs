@@ -1,10 +1,25 @@
+use std::{collections::HashSet, net::TcpListener, time::Duration};
+
 use clap::Args;
-use log::error;
+use log::{error, info, warn};
 
+use super::{
+    backend::{BackendKind, NodeBackend},
+    health::{wait_for_nodes, NodeEndpoints},
+    process::ProcessBackend,
+    profile::{NodePorts, Profile},
+};
 use crate::{
-    constants::{DEFAULT_AXON_DATA_VOLUME, DEFAULT_AXON_NETWORK_NAME, DEFAULT_AXON_PATH},
-    docker::{DockerApi, StartAxonArgs},
+    constants::{
+        DEFAULT_AXON_DATA_VOLUME, DEFAULT_AXON_NETWORK_NAME, DEFAULT_AXON_PATH, DEFAULT_NODE_HOST,
+        DEFAULT_PROFILE, LABEL_ADDRESS, LABEL_COLLECTING_PORT, LABEL_HTTP_PORT, LABEL_NODE,
+        LABEL_P2P_PORT, ROLE_NODE,
+    },
+    docker::{
+        is_local_docker_uri, profile_labels, ContainerResources, DockerApi, Labels, StartAxonArgs,
+    },
     types::{DockerArgs, Result},
+    utils::parse_size,
 };
 
 #[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
@@ -29,23 +44,136 @@ pub struct StartNodesArgs {
     #[clap(short, long, default_value = "8000")]
     port: u32,
 
-    /// the starting of axon nodes's collecting ports
-    #[clap(short, long, default_value = "8900")]
+    /// the starting of axon nodes' collecting ports
+    #[clap(short, long, default_value = "8100")]
     collecting_port: u32,
 
     /// the starting of axon nodes' p2p ports
     #[clap(short = '2', long, default_value = "10000")]
     p2p_port: u32,
 
+    /// pick the next free ports instead of failing when ports are in use
+    #[clap(long)]
+    auto_ports: bool,
+
+    /// seconds to wait for nodes to serve RPC and metrics after starting
+    #[clap(short, long, default_value = "60")]
+    timeout: u64,
+
+    /// return as soon as containers have started without waiting for nodes
+    #[clap(long)]
+    no_wait: bool,
+
+    /// the profile id used to label containers, networks and volumes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// the image of Axon, defaults to the one recorded in the profile
+    #[clap(long)]
+    image: Option<String>,
+
+    /// the tag of Axon's image, defaults to the one recorded in the profile
+    #[clap(long)]
+    tag: Option<String>,
+
+    /// where to run nodes, defaults to the one recorded in the profile
+    #[clap(long, arg_enum)]
+    backend: Option<BackendKind>,
+
+    /// the axon binary run by the process backend, defaults to the one
+    /// recorded in the profile
+    #[clap(long)]
+    binary: Option<String>,
+
+    #[clap(flatten)]
+    resource_args: NodeResourcesArgs,
+
     #[clap(flatten)]
     docker_args: DockerArgs,
 }
 
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct NodeResourcesArgs {
+    /// the relative CPU weight of each node
+    #[clap(long)]
+    cpu_shares: Option<u32>,
+
+    /// the number of CPUs each node may use, e.g. 1.5
+    #[clap(long)]
+    cpus: Option<String>,
+
+    /// the memory limit of each node, e.g. 512m or 2g
+    #[clap(long)]
+    memory: Option<String>,
+
+    /// ulimits of each node seperated by ',', e.g. nofile=65535,nproc=4096
+    #[clap(long, value_delimiter = ',')]
+    ulimit: Vec<String>,
+
+    /// the restart policy of nodes: no, always, unless-stopped or
+    /// on-failure[:max-retries], defaults to always
+    #[clap(long)]
+    restart: Option<String>,
+}
+
+impl NodeResourcesArgs {
+    fn parse(&self) -> Result<ContainerResources> {
+        let cpus = match &self.cpus {
+            Some(cpus) => Some(
+                cpus.parse::<f64>()
+                    .map_err(|_| format!("Invalid number of CPUs {cpus}"))?,
+            ),
+            None => None,
+        };
+        let memory = self.memory.as_deref().map(parse_size).transpose()?;
+
+        let ulimits = self
+            .ulimit
+            .iter()
+            .map(|ulimit| {
+                let (name, val) = ulimit.split_once('=').ok_or_else(|| {
+                    format!("Invalid ulimit {ulimit}, expected e.g. nofile=65535")
+                })?;
+                if !["nofile", "nproc", "core", "stack", "memlock"].contains(&name) {
+                    return Err(format!("Unsupported ulimit {name}").into());
+                }
+
+                Ok((name.to_string(), val.parse()?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let restart_policy = match &self.restart {
+            Some(restart) => {
+                let (policy, max_retries) = match restart.split_once(':') {
+                    Some((policy, max_retries)) => (policy, max_retries.parse()?),
+                    None => (restart.as_str(), 0),
+                };
+                if !["no", "always", "unless-stopped", "on-failure"].contains(&policy)
+                    || (max_retries > 0 && policy != "on-failure")
+                {
+                    return Err(format!("Invalid restart policy {restart}").into());
+                }
+
+                Some((policy.to_string(), max_retries))
+            }
+            None => None,
+        };
+
+        Ok(ContainerResources {
+            cpu_shares: self.cpu_shares,
+            cpus,
+            memory,
+            ulimits,
+            restart_policy,
+        })
+    }
+}
+
 #[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct OperateNodeContainersArgs {
-    /// number of axon nodes
-    #[clap(short, long, default_value = "1")]
-    number: u32,
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
 
     #[clap(flatten)]
     docker_args: DockerArgs,
@@ -53,9 +181,9 @@ pub struct OperateNodeContainersArgs {
 
 #[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct RmNodeContainersArgs {
-    /// number of axon nodes
-    #[clap(short, long, default_value = "1")]
-    number: u32,
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
 
     /// force the removal of containers
     #[clap(short, long)]
@@ -65,6 +193,263 @@ pub struct RmNodeContainersArgs {
     docker_args: DockerArgs,
 }
 
+/// A node of a profile, discovered through the labels of its container or the
+/// metadata of its process
+pub struct NodeContainer {
+    pub name:            String,
+    pub index:           u32,
+    pub state:           String,
+    pub http_port:       Option<u32>,
+    pub p2p_port:        Option<u32>,
+    pub collecting_port: Option<u32>,
+    /// the address of the host, if the node runs on a remote host
+    pub address:         Option<String>,
+    pub resources:       ContainerResources,
+}
+
+impl NodeContainer {
+    pub fn endpoints(&self) -> Result<NodeEndpoints> {
+        let address = self.address.as_deref().unwrap_or(DEFAULT_NODE_HOST);
+
+        match (self.http_port, self.collecting_port) {
+            (Some(http_port), Some(collecting_port)) => Ok(NodeEndpoints {
+                name:        self.name.clone(),
+                http_url:    format!("http://{}:{}", address, http_port),
+                metrics_url: format!("http://{}:{}/metrics", address, collecting_port),
+            }),
+            _ => Err(format!("Ports of container {} are not labeled", self.name).into()),
+        }
+    }
+}
+
+fn node_labels(
+    profile: &Profile,
+    index: u32,
+    port: u32,
+    p2p_port: u32,
+    collecting_port: u32,
+    resources: &ContainerResources,
+) -> Labels {
+    let mut labels = profile_labels(&profile.id, ROLE_NODE);
+    labels.extend(resources.labels());
+    if let Some(host) = profile.host(index) {
+        labels.insert(LABEL_ADDRESS.to_string(), host.address.clone());
+    }
+    labels.insert(LABEL_NODE.to_string(), index.to_string());
+    labels.insert(LABEL_HTTP_PORT.to_string(), port.to_string());
+    labels.insert(LABEL_P2P_PORT.to_string(), p2p_port.to_string());
+    labels.insert(
+        LABEL_COLLECTING_PORT.to_string(),
+        collecting_port.to_string(),
+    );
+
+    labels
+}
+
+/// The arguments to create the container of a node, which are shared by
+/// starting and upgrading nodes
+pub fn node_start_args(
+    profile: &Profile,
+    index: u32,
+    port: u32,
+    p2p_port: u32,
+    collecting_port: u32,
+    resources: &ContainerResources,
+) -> StartAxonArgs<String, String, &'static str, &str, &str, &str> {
+    StartAxonArgs {
+        name: format!("axon{index}"),
+        config_path: format!("config_{index}.toml"),
+        genesis_path: "genesis.json",
+        data_volume: &profile.data_volume,
+        path: &profile.path,
+        port,
+        collecting_port,
+        p2p_port,
+        network: &profile.network,
+        labels: node_labels(profile, index, port, p2p_port, collecting_port, resources),
+        image: profile.image.clone(),
+        tag: profile.tag.clone(),
+        resources: resources.clone(),
+    }
+}
+
+/// The backends running the nodes of a profile, paired with their Docker uris,
+/// which are `None` for local processes
+async fn profile_backends(
+    profile: &Profile,
+    docker_args: &DockerArgs,
+) -> Result<Vec<(Option<String>, Box<dyn NodeBackend>)>> {
+    if profile.backend == BackendKind::Process {
+        let backend = ProcessBackend::new(&profile.id);
+        return Ok(vec![(None, Box::new(backend) as Box<dyn NodeBackend>)]);
+    }
+
+    let mut backends = Vec::new();
+    for uri in profile.docker_uris(&docker_args.docker_uri) {
+        let docker_api = DockerApi::new(profile.docker_args(uri, docker_args)).await?;
+        backends.push((
+            Some(uri.to_string()),
+            Box::new(docker_api) as Box<dyn NodeBackend>,
+        ));
+    }
+
+    Ok(backends)
+}
+
+/// Find the nodes of a profile on all of its backends
+async fn find_nodes_on_hosts(
+    profile: &str,
+    docker_args: &DockerArgs,
+) -> Result<Vec<(Box<dyn NodeBackend>, Vec<NodeContainer>)>> {
+    let node_profile = Profile::load_or_default(profile)?;
+    let mut backends = Vec::new();
+
+    for (_, backend) in profile_backends(&node_profile, docker_args).await? {
+        let nodes = backend.list_nodes(profile).await?;
+        backends.push((backend, nodes));
+    }
+
+    if backends.iter().all(|(_, nodes)| nodes.is_empty()) {
+        error!("No Axon node of profile {profile} found, see \"axon start\"");
+    }
+
+    Ok(backends)
+}
+
+/// Find the nodes of a profile on each of its Docker daemons, the default one
+/// first, for the commands that work on containers
+pub async fn find_nodes_on_daemons(
+    profile: &str,
+    docker_args: &DockerArgs,
+) -> Result<Vec<(DockerApi, Vec<NodeContainer>)>> {
+    let node_profile = Profile::load_or_default(profile)?;
+    let mut daemons = Vec::new();
+
+    for uri in node_profile.docker_uris(&docker_args.docker_uri) {
+        let docker_api = DockerApi::new(node_profile.docker_args(uri, docker_args)).await?;
+        let nodes = docker_api.list_nodes(profile).await?;
+        daemons.push((docker_api, nodes));
+    }
+
+    if daemons.iter().all(|(_, nodes)| nodes.is_empty()) {
+        error!("No Axon node of profile {profile} found, see \"axon start\"");
+    }
+
+    Ok(daemons)
+}
+
+/// The nodes found on the daemons paired with the daemon each one runs on,
+/// sorted by their indexes
+pub fn daemon_nodes(
+    daemons: &[(DockerApi, Vec<NodeContainer>)],
+) -> Vec<(&DockerApi, &NodeContainer)> {
+    let mut nodes = daemons
+        .iter()
+        .flat_map(|(docker_api, nodes)| nodes.iter().map(move |node| (docker_api, node)))
+        .collect::<Vec<_>>();
+    nodes.sort_by_key(|(_, node)| node.index);
+
+    nodes
+}
+
+/// Remove the chain data of a profile on all of its backends
+pub async fn clean_nodes(
+    profile: &str,
+    data_volume: Option<String>,
+    docker_args: &DockerArgs,
+) -> Result<()> {
+    let mut profile = Profile::load_or_default(profile)?;
+    if let Some(data_volume) = data_volume {
+        profile.data_volume = data_volume;
+    }
+
+    for (_, backend) in profile_backends(&profile, docker_args).await? {
+        backend.clean(&profile).await?;
+    }
+
+    Ok(())
+}
+
+fn is_port_available(port: u32) -> bool {
+    match u16::try_from(port) {
+        Ok(port) => TcpListener::bind(("0.0.0.0", port)).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Take the port if it is free, or the next free one when `auto` is set, the
+/// availability of ports on remote hosts can't be checked
+fn allocate_port(port: u32, taken: &mut HashSet<u32>, auto: bool, local: bool) -> Result<u32> {
+    let mut candidate = port;
+
+    while taken.contains(&candidate) || (local && !is_port_available(candidate)) {
+        if !auto {
+            return Err(format!(
+                "Port {port} is already in use, use \"--auto-ports\" to pick free ports"
+            )
+            .into());
+        }
+
+        candidate += 1;
+        if candidate > u32::from(u16::MAX) {
+            return Err(format!("No free port found from {port}").into());
+        }
+    }
+
+    if candidate != port {
+        warn!("Port {port} is already in use, using {candidate} instead");
+    }
+    taken.insert(candidate);
+
+    Ok(candidate)
+}
+
+/// Decide the host ports of nodes, the nodes that already have containers
+/// keep their ports and the others get checked ones
+fn allocate_ports(
+    existing: &[NodeContainer],
+    indexes: &[u32],
+    (port, p2p_port, collecting_port): (u32, u32, u32),
+    auto: bool,
+    local: bool,
+) -> Result<Vec<NodePorts>> {
+    let mut taken = existing
+        .iter()
+        .flat_map(|node| [node.http_port, node.p2p_port, node.collecting_port])
+        .flatten()
+        .collect::<HashSet<_>>();
+
+    indexes
+        .iter()
+        .map(|&index| {
+            let i = index - 1;
+            let node = existing.iter().find(|node| node.index == index);
+
+            if let Some(NodeContainer {
+                http_port: Some(http),
+                p2p_port: Some(p2p),
+                collecting_port: Some(collecting),
+                ..
+            }) = node
+            {
+                return Ok(NodePorts {
+                    index,
+                    http: *http,
+                    p2p: *p2p,
+                    collecting: *collecting,
+                });
+            }
+
+            Ok(NodePorts {
+                index,
+                http: allocate_port(port + i, &mut taken, auto, local)?,
+                p2p: allocate_port(p2p_port + i, &mut taken, auto, local)?,
+                collecting: allocate_port(collecting_port + i, &mut taken, auto, local)?,
+            })
+        })
+        .collect()
+}
+
 pub async fn start_nodes(args: StartNodesArgs) -> Result<()> {
     let StartNodesArgs {
         network,
@@ -73,16 +458,41 @@ pub async fn start_nodes(args: StartNodesArgs) -> Result<()> {
         port,
         collecting_port,
         p2p_port,
-        docker_args: DockerArgs { docker_uri },
+        auto_ports,
+        timeout,
+        no_wait,
+        profile: profile_id,
+        image,
+        tag,
+        backend,
+        binary,
+        resource_args,
+        docker_args,
         data_volume,
     } = args;
+    let docker_uri = docker_args.docker_uri.clone();
 
-    let docker_api = DockerApi::new(docker_uri)?;
+    let resources = resource_args.parse()?;
 
-    docker_api.ensure_network(&network).await?;
+    let mut profile = Profile::load_or_default(&profile_id)?;
+    if let Some(image) = image {
+        profile.image = image;
+    }
+    if let Some(tag) = tag {
+        profile.tag = tag;
+    }
+    if let Some(backend) = backend {
+        profile.backend = backend;
+    }
+    if let Some(binary) = binary {
+        profile.binary = binary;
+    }
+    profile.path = path;
+    profile.network = network;
+    profile.data_volume = data_volume;
 
     if !(0..num).all(|i| {
-        let path: &std::path::Path = path.as_ref();
+        let path: &std::path::Path = profile.path.as_ref();
         path.join("nodes")
             .join(format!("config_{}.toml", i + 1))
             .exists()
@@ -91,54 +501,162 @@ pub async fn start_nodes(args: StartNodesArgs) -> Result<()> {
         return Ok(());
     }
 
-    Ok(futures::future::join_all((0..num).map(|i| {
-        docker_api.start_axon(StartAxonArgs {
-            name:            format!("axon{}", i + 1),
-            config_path:     format!("config_{}.toml", i + 1),
-            genesis_path:    "genesis.json",
-            data_volume:     &data_volume,
-            path:            &path,
-            port:            port + i,
-            collecting_port: collecting_port + i,
-            p2p_port:        p2p_port + i,
-            network:         &network,
-        })
-    }))
-    .await
-    .into_iter()
-    .collect::<std::result::Result<(), _>>()?)
+    let mut backends = Vec::new();
+    let mut ports = Vec::new();
+
+    for (uri, backend) in profile_backends(&profile, &docker_args).await? {
+        let indexes = (1..=num)
+            .filter(|index| {
+                uri.as_ref()
+                    .map_or(true, |uri| profile.docker_uri(*index, &docker_uri) == uri)
+            })
+            .collect::<Vec<_>>();
+        if indexes.is_empty() {
+            continue;
+        }
+
+        let local = uri.as_deref().map_or(true, is_local_docker_uri);
+        backend.prepare(&profile).await?;
+
+        let allocated = allocate_ports(
+            &backend.list_nodes(&profile_id).await?,
+            &indexes,
+            (port, p2p_port, collecting_port),
+            auto_ports,
+            local,
+        )?;
+
+        futures::future::join_all(
+            allocated
+                .iter()
+                .map(|ports| backend.start_node(&profile, ports, &resources)),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<()>>()?;
+
+        backends.push((backend, allocated.clone()));
+        ports.extend(allocated);
+    }
+
+    // the nodes not started this time keep their ports
+    let started = ports
+        .iter()
+        .map(|ports| ports.index)
+        .collect::<HashSet<_>>();
+    profile
+        .ports
+        .retain(|ports| !started.contains(&ports.index));
+    profile.ports.extend(ports);
+    profile.ports.sort_by_key(|ports| ports.index);
+    profile.save()?;
+    for ports in profile
+        .ports
+        .iter()
+        .filter(|ports| started.contains(&ports.index))
+    {
+        info!(
+            "axon{} on {}, http port: {}, p2p port: {}, collecting port: {}",
+            ports.index,
+            profile.node_address(ports.index),
+            ports.http,
+            ports.p2p,
+            ports.collecting
+        );
+    }
+
+    if no_wait {
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for (backend, ports) in backends {
+        let endpoints = ports
+            .iter()
+            .map(|ports| {
+                let address = profile.node_address(ports.index);
+
+                NodeEndpoints {
+                    name:        format!("axon{}", ports.index),
+                    http_url:    format!("http://{}:{}", address, ports.http),
+                    metrics_url: format!("http://{}:{}/metrics", address, ports.collecting),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        failed += wait_for_nodes(backend.as_ref(), &endpoints, Duration::from_secs(timeout))
+            .await?
+            .iter()
+            .filter(|readiness| !readiness.is_ready())
+            .count();
+    }
+
+    if failed > 0 {
+        return Err(format!("{failed} of {num} node(s) failed the readiness check").into());
+    }
+
+    Ok(())
 }
 
 pub async fn rm_nodes(args: RmNodeContainersArgs) -> Result<()> {
     let RmNodeContainersArgs {
-        number,
+        profile,
         force,
-        docker_args: DockerArgs { docker_uri },
+        docker_args,
     } = args;
 
-    Ok(DockerApi::new(docker_uri)?
-        .remove_containers((1..number + 1).map(|i| format!("axon{i}")), force)
-        .await?)
+    for (backend, nodes) in find_nodes_on_hosts(&profile, &docker_args).await? {
+        futures::future::join_all(
+            nodes
+                .iter()
+                .map(|node| backend.remove_node(&node.name, force)),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<()>>()?;
+    }
+
+    Ok(())
 }
 
 pub async fn stop_nodes(args: OperateNodeContainersArgs) -> Result<()> {
     let OperateNodeContainersArgs {
-        number,
-        docker_args: DockerArgs { docker_uri },
+        profile,
+        docker_args,
     } = args;
 
-    Ok(DockerApi::new(docker_uri)?
-        .stop_containers((1..number + 1).map(|i| format!("axon{i}")))
-        .await?)
+    for (backend, nodes) in find_nodes_on_hosts(&profile, &docker_args).await? {
+        futures::future::join_all(nodes.iter().map(|node| backend.stop_node(&node.name)))
+            .await
+            .into_iter()
+            .collect::<Result<()>>()?;
+    }
+
+    Ok(())
 }
 
 pub async fn ps_nodes(args: OperateNodeContainersArgs) -> Result<()> {
     let OperateNodeContainersArgs {
-        number,
-        docker_args: DockerArgs { docker_uri },
+        profile,
+        docker_args,
     } = args;
 
-    Ok(DockerApi::new(docker_uri)?
-        .inspect_containers((1..number + 1).map(|i| format!("axon{i}")))
-        .await?)
+    let port_or_dash =
+        |port: Option<u32>| port.map_or_else(|| "-".to_string(), |port| port.to_string());
+
+    for (_, nodes) in find_nodes_on_hosts(&profile, &docker_args).await? {
+        for node in nodes {
+            info!(
+                "Node {} is {} on {}, http port: {}, p2p port: {}, collecting port: {}",
+                node.name,
+                node.state,
+                node.address.as_deref().unwrap_or(DEFAULT_NODE_HOST),
+                port_or_dash(node.http_port),
+                port_or_dash(node.p2p_port),
+                port_or_dash(node.collecting_port),
+            );
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,289 @@
+use std::{
+    fs::{
+        copy, create_dir_all, read_dir, read_to_string, remove_dir_all, remove_file, write, File,
+    },
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use log::{error, info};
+use nix::{
+    sys::signal::{kill, Signal},
+    unistd::Pid,
+};
+
+use super::{
+    backend::NodeBackend,
+    nodes::NodeContainer,
+    profile::{NodePorts, Profile},
+};
+use crate::{
+    constants::{DEFAULT_NODE_HOST, DEFAULT_PROCESSES_PATH, DOCKER_BRIDGE_ADDRESS},
+    docker::ContainerResources,
+    types::Result,
+    utils::{from_json_file, to_json_file},
+};
+
+/// The offset from the http port to the websocket port of a node, so that
+/// nodes on the same machine don't share it
+const WS_PORT_OFFSET: u32 = 1000;
+
+/// Seconds to wait for a node to exit after SIGTERM before killing it
+const STOP_TIMEOUT: u64 = 10;
+
+/// Runs nodes as local processes of an axon binary, the config, data, logs and
+/// PID file of each node are kept in `<processes path>/<profile>/axon<index>`
+pub struct ProcessBackend {
+    root: PathBuf,
+}
+
+fn is_running(pid: Pid) -> bool {
+    kill(pid, None).is_ok()
+}
+
+fn signal(pid: Pid, signal: Signal) -> Result<()> {
+    kill(pid, signal).map_err(|err| format!("Failed to send {signal} to process {pid}: {err}"))?;
+
+    Ok(())
+}
+
+/// Rewrite the ports of a config made for containers, where every node
+/// listens on the same ports of its own network namespace
+fn local_config(config: &str, ports: &NodePorts) -> String {
+    config
+        .replace(
+            "http_listening_address = \"0.0.0.0:8000\"",
+            &format!("http_listening_address = \"0.0.0.0:{}\"", ports.http),
+        )
+        .replace(
+            "ws_listening_address = \"0.0.0.0:8010\"",
+            &format!(
+                "ws_listening_address = \"0.0.0.0:{}\"",
+                ports.http + WS_PORT_OFFSET
+            ),
+        )
+        .replace(
+            "listening_address = \"/ip4/0.0.0.0/tcp/8001\"",
+            &format!("listening_address = \"/ip4/0.0.0.0/tcp/{}\"", ports.p2p),
+        )
+        .replace(
+            "listening_address = \"0.0.0.0:8100\"",
+            &format!("listening_address = \"0.0.0.0:{}\"", ports.collecting),
+        )
+        .replace(
+            &format!("/ip4/{DOCKER_BRIDGE_ADDRESS}/"),
+            &format!("/ip4/{DEFAULT_NODE_HOST}/"),
+        )
+}
+
+impl ProcessBackend {
+    pub fn new(profile: &str) -> Self {
+        Self {
+            root: Path::new(*DEFAULT_PROCESSES_PATH).join(profile),
+        }
+    }
+
+    fn node_path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    fn pid(&self, name: &str) -> Option<Pid> {
+        read_to_string(self.node_path(name).join("axon.pid"))
+            .ok()
+            .and_then(|pid| pid.trim().parse().ok())
+            .map(Pid::from_raw)
+    }
+
+    fn running_pid(&self, name: &str) -> Option<Pid> {
+        self.pid(name).filter(|pid| is_running(*pid))
+    }
+}
+
+#[async_trait]
+impl NodeBackend for ProcessBackend {
+    async fn prepare(&self, _profile: &Profile) -> Result<()> {
+        create_dir_all(&self.root)?;
+
+        Ok(())
+    }
+
+    async fn start_node(
+        &self,
+        profile: &Profile,
+        ports: &NodePorts,
+        resources: &ContainerResources,
+    ) -> Result<()> {
+        if *resources != ContainerResources::default() {
+            return Err(
+                "The process backend can't apply limits or restart policies, they \
+                        are only supported by the docker backend"
+                    .into(),
+            );
+        }
+
+        let name = format!("axon{}", ports.index);
+        let node_path = self.node_path(&name);
+        let nodes_path = Path::new(&profile.path).join("nodes");
+
+        if let Some(pid) = self.running_pid(&name) {
+            error!("Node {} is already running, pid: {}", name, pid);
+            return Ok(());
+        }
+
+        create_dir_all(&node_path)?;
+        let config = read_to_string(nodes_path.join(format!("config_{}.toml", ports.index)))?;
+        write(
+            node_path.join("config.toml"),
+            local_config(&config, ports).as_bytes(),
+        )?;
+        copy(
+            nodes_path.join("default.db-options"),
+            node_path.join("default.db-options"),
+        )?;
+        to_json_file(ports, node_path.join("node.json"))?;
+
+        // a process group of its own keeps the node out of the signals sent
+        // to the CLI, e.g. CTRL-C, so it keeps running after the CLI exits
+        let log = File::create(node_path.join("axon.log"))?;
+        let mut child = Command::new(&profile.binary)
+            .arg("-c=config.toml")
+            .arg(format!(
+                "-g={}",
+                nodes_path.join("genesis.json").to_str().unwrap()
+            ))
+            .current_dir(&node_path)
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .process_group(0)
+            .spawn()
+            .map_err(|err| format!("Failed to start {} with {}: {}", name, profile.binary, err))?;
+        let pid = child.id().to_string();
+        // reap the node when it exits while the CLI lives on, e.g. in the
+        // interactive mode, or it stays a zombie taken for a running node
+        std::thread::spawn(move || child.wait());
+
+        write(node_path.join("axon.pid"), pid.as_bytes())?;
+        info!("Node {} has started, pid: {}", name, pid);
+
+        Ok(())
+    }
+
+    async fn list_nodes(&self, _profile: &str) -> Result<Vec<NodeContainer>> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut nodes = Vec::new();
+        for entry in read_dir(&self.root)? {
+            let path = entry?.path();
+            let metadata_path = path.join("node.json");
+            if !metadata_path.exists() {
+                continue;
+            }
+
+            let ports: NodePorts = from_json_file(metadata_path)?;
+            let name = format!("axon{}", ports.index);
+            let state = if self.running_pid(&name).is_some() {
+                "running"
+            } else {
+                "exited"
+            };
+
+            nodes.push(NodeContainer {
+                name,
+                index: ports.index,
+                state: state.to_string(),
+                http_port: Some(ports.http),
+                p2p_port: Some(ports.p2p),
+                collecting_port: Some(ports.collecting),
+                address: None,
+                resources: ContainerResources::default(),
+            });
+        }
+        nodes.sort_by_key(|node| node.index);
+
+        Ok(nodes)
+    }
+
+    async fn stop_node(&self, name: &str) -> Result<()> {
+        let pid = match self.running_pid(name) {
+            Some(pid) => pid,
+            None => {
+                error!("Node {} is not running", name);
+                return Ok(());
+            }
+        };
+
+        info!("Stopping node {}, pid: {}...", name, pid);
+        signal(pid, Signal::SIGTERM)?;
+
+        let deadline = Instant::now() + Duration::from_secs(STOP_TIMEOUT);
+        while is_running(pid) {
+            if Instant::now() >= deadline {
+                signal(pid, Signal::SIGKILL)?;
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+
+        remove_file(self.node_path(name).join("axon.pid"))?;
+        info!("Node {} has stopped", name);
+
+        Ok(())
+    }
+
+    async fn remove_node(&self, name: &str, force: bool) -> Result<()> {
+        if self.running_pid(name).is_some() {
+            if !force {
+                error!("Can't remove running node {}", name);
+                return Ok(());
+            }
+
+            self.stop_node(name).await?;
+        }
+
+        // keep the chain data and logs like the volumes of containers
+        let node_path = self.node_path(name);
+        for file in ["node.json", "config.toml", "axon.pid"] {
+            let path = node_path.join(file);
+            if path.exists() {
+                remove_file(path)?;
+            }
+        }
+        info!("Removed node {}", name);
+
+        Ok(())
+    }
+
+    async fn tail_logs(&self, name: &str, lines: usize) -> Result<Vec<String>> {
+        let logs = read_to_string(self.node_path(name).join("axon.log"))?;
+        let logs = logs.lines().collect::<Vec<_>>();
+
+        Ok(logs[logs.len().saturating_sub(lines)..]
+            .iter()
+            .map(|line| line.to_string())
+            .collect())
+    }
+
+    async fn clean(&self, _profile: &Profile) -> Result<()> {
+        if !self.root.exists() {
+            error!("No chain data in {}", self.root.to_str().unwrap());
+            return Ok(());
+        }
+
+        for entry in read_dir(&self.root)? {
+            let data_path = entry?.path().join("nodes");
+            if data_path.exists() {
+                remove_dir_all(&data_path)?;
+                info!("Removed {}", data_path.to_str().unwrap());
+            }
+        }
+
+        Ok(())
+    }
+}
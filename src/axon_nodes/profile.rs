@@ -0,0 +1,148 @@
+use std::{
+    fs::create_dir_all,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::backend::BackendKind;
+use crate::{
+    constants::{
+        AXON_IMAGE_NAME, AXON_IMAGE_TAG, DEFAULT_AXON_BINARY, DEFAULT_AXON_DATA_VOLUME,
+        DEFAULT_AXON_NETWORK_NAME, DEFAULT_AXON_PATH, DEFAULT_NODE_HOST, DEFAULT_PROFILES_PATH,
+    },
+    types::{DockerArgs, Result},
+    utils::{from_json_file, to_json_file},
+};
+
+/// The host ports published by the container of a node
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct NodePorts {
+    pub index:      u32,
+    pub http:       u32,
+    pub p2p:        u32,
+    pub collecting: u32,
+}
+
+/// The Docker daemon a node runs on and the address other nodes reach it by
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+pub struct NodeHost {
+    pub index:         u32,
+    pub docker_uri:    String,
+    pub address:       String,
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+}
+
+/// The settings a devnet was started with, so that later commands can operate
+/// on it without repeating them
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(default)]
+pub struct Profile {
+    pub id:          String,
+    pub image:       String,
+    pub tag:         String,
+    pub path:        String,
+    pub network:     String,
+    pub data_volume: String,
+    pub ports:       Vec<NodePorts>,
+    pub hosts:       Vec<NodeHost>,
+    pub backend:     BackendKind,
+    /// the axon binary run by the process backend
+    pub binary:      String,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            id:          "".to_string(),
+            image:       AXON_IMAGE_NAME.to_string(),
+            tag:         AXON_IMAGE_TAG.to_string(),
+            path:        DEFAULT_AXON_PATH.to_string(),
+            network:     DEFAULT_AXON_NETWORK_NAME.to_string(),
+            data_volume: DEFAULT_AXON_DATA_VOLUME.to_string(),
+            ports:       Vec::new(),
+            hosts:       Vec::new(),
+            backend:     BackendKind::Docker,
+            binary:      DEFAULT_AXON_BINARY.to_string(),
+        }
+    }
+}
+
+impl Profile {
+    fn file_path(id: &str) -> PathBuf {
+        Path::new(*DEFAULT_PROFILES_PATH).join(format!("{id}.json"))
+    }
+
+    pub fn load(id: &str) -> Result<Option<Self>> {
+        let path = Self::file_path(id);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(from_json_file(path)?))
+    }
+
+    pub fn load_or_default(id: &str) -> Result<Self> {
+        Ok(Self::load(id)?.unwrap_or_else(|| Self {
+            id: id.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    pub fn host(&self, index: u32) -> Option<&NodeHost> {
+        self.hosts.iter().find(|host| host.index == index)
+    }
+
+    /// The Docker daemon of a node, nodes without a host run on the default
+    /// daemon
+    pub fn docker_uri<'a>(&'a self, index: u32, default: &'a str) -> &'a str {
+        self.host(index)
+            .map_or(default, |host| host.docker_uri.as_str())
+    }
+
+    /// The address to reach the published ports of a node from this machine
+    pub fn node_address(&self, index: u32) -> &str {
+        self.host(index)
+            .map_or(DEFAULT_NODE_HOST, |host| host.address.as_str())
+    }
+
+    /// All Docker daemons of the profile without duplicates, the default one
+    /// first
+    pub fn docker_uris<'a>(&'a self, default: &'a str) -> Vec<&'a str> {
+        let mut uris = vec![default];
+
+        for host in self.hosts.iter() {
+            if !uris.contains(&host.docker_uri.as_str()) {
+                uris.push(&host.docker_uri);
+            }
+        }
+
+        uris
+    }
+
+    /// The arguments to connect to one of the Docker daemons of the profile,
+    /// the hosts take the TLS certificates recorded with them
+    pub fn docker_args(&self, uri: &str, default: &DockerArgs) -> DockerArgs {
+        if uri == default.docker_uri {
+            return default.clone();
+        }
+
+        DockerArgs {
+            docker_uri:     uri.to_string(),
+            tls_cert_path:  self
+                .hosts
+                .iter()
+                .find(|host| host.docker_uri == uri)
+                .and_then(|host| host.tls_cert_path.clone()),
+            allow_insecure: default.allow_insecure,
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        create_dir_all(*DEFAULT_PROFILES_PATH)?;
+
+        to_json_file(self, Self::file_path(&self.id))
+    }
+}
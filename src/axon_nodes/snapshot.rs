@@ -0,0 +1,403 @@
+use std::{
+    fs::{create_dir_all, read_dir},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use axon_protocol::types::H256;
+use chrono::Local;
+use clap::{Args, Subcommand};
+use docker_api::opts::ContainerCreateOpts;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    health::wait_for_nodes,
+    nodes::{daemon_nodes, find_nodes_on_daemons, NodeContainer},
+    profile::Profile,
+};
+use crate::{
+    constants::{
+        ARCHIVE_IMAGE_NAME, ARCHIVE_IMAGE_TAG, DEFAULT_PROFILE, DEFAULT_SNAPSHOTS_PATH,
+        ROLE_SNAPSHOT, SNAPSHOT_CONTAINER_NAME,
+    },
+    docker::{profile_labels, DockerApi},
+    rpc::RpcClient,
+    types::{DockerArgs, Result},
+    utils::{from_json_file, to_json_file},
+};
+
+#[derive(Subcommand, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum SnapshotActions {
+    /// Archive the chain data and config files of the nodes, the nodes are
+    /// stopped while archiving
+    Create(SnapshotArgs),
+
+    /// Stop the nodes and restore their chain data and config files
+    Restore(SnapshotArgs),
+
+    /// List snapshots
+    List,
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct SnapshotArgs {
+    /// the name of the snapshot
+    name: String,
+
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// seconds to wait for nodes to be ready after restoring
+    #[clap(short, long, default_value = "60")]
+    timeout: u64,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SnapshotMetadata {
+    name:         String,
+    created_at:   String,
+    profile:      String,
+    height:       Option<u64>,
+    genesis_hash: Option<H256>,
+    image:        String,
+    tag:          String,
+    nodes:        Vec<String>,
+}
+
+/// The name is a file name and goes into the scripts of the archiver, so only
+/// `[A-Za-z0-9._-]` are allowed
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err(format!(
+            "Invalid snapshot name \"{name}\", use letters, digits, '.', '_' and '-'"
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn metadata_path(name: &str) -> PathBuf {
+    Path::new(*DEFAULT_SNAPSHOTS_PATH).join(format!("{name}.json"))
+}
+
+/// The archive of the nodes on one daemon of the profile, which is written on
+/// the host of that daemon, the default daemon comes first
+fn archive_name(name: &str, daemon: usize) -> String {
+    match daemon {
+        0 => format!("{name}.tar.gz"),
+        daemon => format!("{name}.{daemon}.tar.gz"),
+    }
+}
+
+/// Query the highest block and the genesis block hash before the nodes stop
+async fn chain_state(nodes: &[(&DockerApi, &NodeContainer)]) -> (Option<u64>, Option<H256>) {
+    let mut height = None;
+    let mut genesis_hash = None;
+
+    for (_, node) in nodes {
+        let rpc = match node.endpoints().and_then(|e| RpcClient::new(e.http_url)) {
+            Ok(rpc) => rpc,
+            Err(_) => continue,
+        };
+
+        if let Ok(node_height) = rpc.block_number().await {
+            height = height.max(Some(node_height));
+        }
+        if genesis_hash.is_none() {
+            genesis_hash = rpc.block_hash(0).await.ok();
+        }
+    }
+
+    (height, genesis_hash)
+}
+
+/// Run the archive image with the data volume and the config files of the
+/// profile mounted under `/snapshot-src` and the snapshots under `/snapshots`
+async fn run_archiver(
+    docker_api: &DockerApi,
+    profile: &Profile,
+    script: String,
+    extra_volumes: Vec<String>,
+) -> Result<bool> {
+    let labels = profile_labels(&profile.id, ROLE_SNAPSHOT);
+    let nodes_path = Path::new(&profile.path).join("nodes");
+    let mut volumes = vec![
+        format!("{}:/snapshot-src/data", profile.data_volume),
+        format!("{}:/snapshot-src/nodes", nodes_path.to_str().unwrap()),
+        format!("{}:/snapshots", *DEFAULT_SNAPSHOTS_PATH),
+    ];
+    volumes.extend(extra_volumes);
+
+    Ok(docker_api
+        .run_once(
+            ARCHIVE_IMAGE_NAME,
+            ARCHIVE_IMAGE_TAG,
+            SNAPSHOT_CONTAINER_NAME,
+            || {
+                ContainerCreateOpts::builder(format!(
+                    "{}:{}",
+                    ARCHIVE_IMAGE_NAME, ARCHIVE_IMAGE_TAG
+                ))
+                .name(SNAPSHOT_CONTAINER_NAME)
+                .labels(&labels)
+                .cmd(["sh", "-c", &script])
+                .volumes(volumes)
+                .build()
+            },
+        )
+        .await?)
+}
+
+/// Start every node, even if some fail, returns the first failure
+async fn start_nodes(nodes: &[(&DockerApi, &NodeContainer)]) -> Result<()> {
+    let mut result = Ok(());
+    for (docker_api, node) in nodes {
+        if let Err(err) = docker_api.start_one_container(&node.name).await {
+            error!("Failed to start {}: {}", node.name, err);
+            if result.is_ok() {
+                result = Err(err.into());
+            }
+        }
+    }
+
+    result
+}
+
+pub async fn exec_snapshot(action: SnapshotActions) -> Result<()> {
+    if let SnapshotActions::Create(args) | SnapshotActions::Restore(args) = &action {
+        validate_name(&args.name)?;
+    }
+
+    match action {
+        SnapshotActions::Create(args) => create_snapshot(args).await,
+        SnapshotActions::Restore(args) => restore_snapshot(args).await,
+        SnapshotActions::List => list_snapshots(),
+    }
+}
+
+async fn create_snapshot(args: SnapshotArgs) -> Result<()> {
+    let SnapshotArgs {
+        name,
+        profile: profile_id,
+        docker_args,
+        ..
+    } = args;
+
+    if metadata_path(&name).exists() {
+        return Err(format!("Snapshot {name} already exists").into());
+    }
+
+    let profile = Profile::load_or_default(&profile_id)?;
+    let daemons = find_nodes_on_daemons(&profile_id, &docker_args).await?;
+    let nodes = daemon_nodes(&daemons);
+    if nodes.is_empty() {
+        return Ok(());
+    }
+
+    let (height, genesis_hash) = chain_state(&nodes).await;
+    if height.is_none() {
+        warn!("Can't get the block height, are the nodes running?");
+    }
+
+    for (docker_api, nodes) in daemons.iter() {
+        docker_api
+            .stop_containers(nodes.iter().map(|node| &node.name))
+            .await?;
+    }
+
+    let metadata = SnapshotMetadata {
+        name: name.clone(),
+        created_at: Local::now().to_rfc3339(),
+        profile: profile_id,
+        height,
+        genesis_hash,
+        image: profile.image.clone(),
+        tag: profile.tag.clone(),
+        nodes: nodes.iter().map(|(_, node)| node.name.clone()).collect(),
+    };
+    create_dir_all(*DEFAULT_SNAPSHOTS_PATH)?;
+    to_json_file(&metadata, metadata_path(&name))?;
+
+    let metadata_volume = format!(
+        "{}:/snapshot-src/metadata.json:ro",
+        metadata_path(&name).to_str().unwrap()
+    );
+
+    let mut archived = Ok(true);
+    for (daemon, (docker_api, nodes)) in daemons.iter().enumerate() {
+        if nodes.is_empty() || !matches!(archived, Ok(true)) {
+            continue;
+        }
+
+        let script = format!(
+            "tar czf /snapshots/{} -C /snapshot-src .",
+            archive_name(&name, daemon)
+        );
+        archived = run_archiver(docker_api, &profile, script, vec![metadata_volume.clone()]).await;
+    }
+
+    let started = start_nodes(&nodes).await;
+
+    if !archived? {
+        std::fs::remove_file(metadata_path(&name))?;
+        return Err(format!("Failed to archive snapshot {name}").into());
+    }
+    started?;
+
+    info!(
+        "Snapshot {} created at height {}, see \"axon snapshot restore {}\"",
+        name,
+        height.map_or_else(|| "unknown".to_string(), |h| h.to_string()),
+        name
+    );
+
+    Ok(())
+}
+
+async fn restore_snapshot(args: SnapshotArgs) -> Result<()> {
+    let SnapshotArgs {
+        name,
+        profile: profile_id,
+        timeout,
+        docker_args,
+    } = args;
+
+    let metadata_path = metadata_path(&name);
+    if !metadata_path.exists() {
+        return Err(format!("Snapshot {name} doesn't exist, see \"axon snapshot list\"").into());
+    }
+    let metadata: SnapshotMetadata = from_json_file(metadata_path)?;
+    // the archives of other daemons are on their hosts, where extracting
+    // them fails if they are missing
+    let archive_path = Path::new(*DEFAULT_SNAPSHOTS_PATH).join(archive_name(&name, 0));
+    if !archive_path.exists() {
+        return Err(format!(
+            "Archive {} of snapshot {name} is missing",
+            archive_path.display()
+        )
+        .into());
+    }
+
+    let profile = Profile::load_or_default(&profile_id)?;
+    if (&profile.image, &profile.tag) != (&metadata.image, &metadata.tag) {
+        warn!(
+            "Snapshot {} was taken with {}:{} but the nodes run {}:{}",
+            name, metadata.image, metadata.tag, profile.image, profile.tag
+        );
+    }
+
+    let daemons = find_nodes_on_daemons(&profile_id, &docker_args).await?;
+    let nodes = daemon_nodes(&daemons);
+    if nodes.is_empty() {
+        return Ok(());
+    }
+
+    // the data of another chain would never be accepted by the nodes
+    match (metadata.genesis_hash, chain_state(&nodes).await.1) {
+        (Some(snapshot_hash), Some(genesis_hash)) if snapshot_hash != genesis_hash => {
+            return Err(format!(
+                "Snapshot {name} is of the chain with genesis {snapshot_hash:?}, but the nodes \
+                 of profile {profile_id} are of {genesis_hash:?}"
+            )
+            .into());
+        }
+        (Some(_), Some(_)) => {}
+        _ => warn!("Can't compare the genesis hash of snapshot {name} with the nodes"),
+    }
+
+    for (docker_api, nodes) in daemons.iter() {
+        docker_api
+            .stop_containers(nodes.iter().map(|node| &node.name))
+            .await?;
+    }
+
+    let mut restored = Ok(true);
+    for (daemon, (docker_api, nodes)) in daemons.iter().enumerate() {
+        if nodes.is_empty() || !matches!(restored, Ok(true)) {
+            continue;
+        }
+
+        // extract to a staging directory first, so that a broken archive
+        // leaves the current data in place
+        let script = format!(
+            "set -e; \
+             mkdir -p /snapshot-staging; \
+             tar xzf /snapshots/{} -C /snapshot-staging; \
+             test -d /snapshot-staging/data; \
+             test -d /snapshot-staging/nodes; \
+             find /snapshot-src/data /snapshot-src/nodes -mindepth 1 -delete; \
+             cp -a /snapshot-staging/data/. /snapshot-src/data/; \
+             cp -a /snapshot-staging/nodes/. /snapshot-src/nodes/",
+            archive_name(&name, daemon)
+        );
+        restored = run_archiver(docker_api, &profile, script, Vec::new()).await;
+    }
+
+    let started = start_nodes(&nodes).await;
+
+    if !restored? {
+        return Err(format!("Failed to restore snapshot {name}").into());
+    }
+    started?;
+
+    for (docker_api, nodes) in daemons.iter().filter(|(_, nodes)| !nodes.is_empty()) {
+        let endpoints = nodes
+            .iter()
+            .map(|node| node.endpoints())
+            .collect::<Result<Vec<_>>>()?;
+        wait_for_nodes(docker_api, &endpoints, Duration::from_secs(timeout)).await?;
+    }
+
+    info!(
+        "Snapshot {} restored, height: {}, genesis hash: {}",
+        name,
+        metadata
+            .height
+            .map_or_else(|| "unknown".to_string(), |h| h.to_string()),
+        metadata
+            .genesis_hash
+            .map_or_else(|| "unknown".to_string(), |h| format!("{h:?}")),
+    );
+
+    Ok(())
+}
+
+fn list_snapshots() -> Result<()> {
+    if !Path::new(*DEFAULT_SNAPSHOTS_PATH).exists() {
+        info!("No snapshot found");
+        return Ok(());
+    }
+
+    for entry in read_dir(*DEFAULT_SNAPSHOTS_PATH)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |ext| ext != "json") {
+            continue;
+        }
+
+        let metadata: SnapshotMetadata = from_json_file(&path)?;
+        println!(
+            "{} created at {}, profile: {}, height: {}, image: {}:{}",
+            metadata.name,
+            metadata.created_at,
+            metadata.profile,
+            metadata
+                .height
+                .map_or_else(|| "unknown".to_string(), |h| h.to_string()),
+            metadata.image,
+            metadata.tag,
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use colored::*;
+
+use super::nodes::{daemon_nodes, find_nodes_on_daemons, NodeContainer};
+use crate::{
+    constants::{DEFAULT_NODE_HOST, DEFAULT_PROFILE},
+    docker::DockerApi,
+    rpc::RpcClient,
+    types::{DockerArgs, Result},
+};
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct StatusArgs {
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// seconds between two refreshes
+    #[clap(short, long, default_value = "3")]
+    interval: u64,
+
+    /// seconds without a new block before a node is considered stalled
+    #[clap(short, long, default_value = "15")]
+    stall_timeout: u64,
+
+    /// number of blocks a node may lag behind the highest node
+    #[clap(short, long, default_value = "3")]
+    lag_threshold: u64,
+
+    /// print the status once instead of refreshing the table
+    #[clap(long)]
+    once: bool,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+#[derive(Debug, Default)]
+struct NodeStatus {
+    name:      String,
+    container: String,
+    height:    Option<u64>,
+    peers:     Option<u64>,
+    syncing:   Option<bool>,
+    tx_pool:   Option<u64>,
+}
+
+async fn query_node(docker_api: &DockerApi, name: String, http_url: Option<String>) -> NodeStatus {
+    let container = match docker_api.container_status(&name).await {
+        Ok(Some(status)) => status,
+        Ok(None) => "missing".to_string(),
+        Err(err) => format!("error: {err}"),
+    };
+    let mut status = NodeStatus {
+        name,
+        container,
+        ..Default::default()
+    };
+
+    let rpc = match http_url.map(RpcClient::new) {
+        Some(Ok(rpc)) => rpc,
+        _ => return status,
+    };
+    let (height, peers, syncing, tx_pool) = futures::join!(
+        rpc.block_number(),
+        rpc.peer_count(),
+        rpc.syncing(),
+        rpc.tx_pool_size(),
+    );
+
+    status.height = height.ok();
+    status.peers = peers.ok();
+    status.syncing = syncing.ok();
+    status.tx_pool = tx_pool.ok();
+
+    status
+}
+
+/// Query the container and the RPC of every node, the RPC fields are left
+/// empty for unreachable nodes
+async fn query_nodes(nodes: &[(&DockerApi, &NodeContainer)]) -> Vec<NodeStatus> {
+    futures::future::join_all(nodes.iter().map(|(docker_api, node)| {
+        query_node(
+            docker_api,
+            node.name.clone(),
+            node.http_port.map(|port| {
+                let address = node.address.as_deref().unwrap_or(DEFAULT_NODE_HOST);
+                format!("http://{}:{}", address, port)
+            }),
+        )
+    }))
+    .await
+}
+
+fn or_dash<T: ToString>(val: Option<T>) -> String {
+    val.map(|v| v.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Keep the last height of each node with the time it was first seen, so
+/// nodes that stop producing blocks can be highlighted
+#[derive(Default)]
+struct HeightTracker {
+    last_seen: HashMap<String, (u64, Instant)>,
+}
+
+impl HeightTracker {
+    fn is_stalled(&mut self, name: &str, height: Option<u64>, stall_timeout: Duration) -> bool {
+        let height = match height {
+            Some(val) => val,
+            None => return true,
+        };
+
+        let now = Instant::now();
+        let (last_height, since) = self
+            .last_seen
+            .entry(name.to_string())
+            .or_insert((height, now));
+
+        if *last_height != height {
+            *last_height = height;
+            *since = now;
+        }
+
+        now.duration_since(*since) >= stall_timeout
+    }
+}
+
+fn render(
+    statuses: &[NodeStatus],
+    tracker: &mut HeightTracker,
+    lag_threshold: u64,
+    stall_timeout: Duration,
+) {
+    let max_height = statuses
+        .iter()
+        .filter_map(|status| status.height)
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "{}",
+        format!(
+            "{:<10} {:<12} {:>10} {:>6} {:>6} {:>8} {:>8}",
+            "NODE", "CONTAINER", "HEIGHT", "LAG", "PEERS", "SYNCING", "TX POOL"
+        )
+        .bold()
+    );
+
+    let mut lagging = 0;
+    let mut stalled = 0;
+    for status in statuses {
+        let lag = status
+            .height
+            .map(|height| max_height.saturating_sub(height));
+        let row = format!(
+            "{:<10} {:<12} {:>10} {:>6} {:>6} {:>8} {:>8}",
+            status.name,
+            status.container,
+            or_dash(status.height),
+            or_dash(lag),
+            or_dash(status.peers),
+            or_dash(status.syncing),
+            or_dash(status.tx_pool),
+        );
+
+        if tracker.is_stalled(&status.name, status.height, stall_timeout) {
+            stalled += 1;
+            println!("{}", row.red());
+        } else if lag.map_or(false, |lag| lag > lag_threshold) {
+            lagging += 1;
+            println!("{}", row.yellow());
+        } else {
+            println!("{}", row);
+        }
+    }
+
+    println!(
+        "\nmax height: {}, lagging: {}, stalled: {}",
+        max_height, lagging, stalled
+    );
+}
+
+pub async fn show_status(args: StatusArgs) -> Result<()> {
+    let StatusArgs {
+        profile,
+        interval,
+        stall_timeout,
+        lag_threshold,
+        once,
+        docker_args,
+    } = args;
+
+    let stall_timeout = Duration::from_secs(stall_timeout);
+    let mut tracker = HeightTracker::default();
+
+    let daemons = find_nodes_on_daemons(&profile, &docker_args).await?;
+    let nodes = daemon_nodes(&daemons);
+    if nodes.is_empty() {
+        return Ok(());
+    }
+
+    loop {
+        let statuses = query_nodes(&nodes).await;
+
+        if !once {
+            // clear the screen and move the cursor to the top left corner
+            print!("\x1B[2J\x1B[1;1H");
+        }
+        render(&statuses, &mut tracker, lag_threshold, stall_timeout);
+
+        if once {
+            return Ok(());
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(interval)) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TestEnv;
+
+    #[tokio::test]
+    async fn unreachable_nodes_are_reported_stalled() {
+        let env = TestEnv::new("unreachable-nodes-are-reported-stalled");
+        env.start_nodes(1).await;
+        let docker_args = DockerArgs {
+            docker_uri: env.uri.clone(),
+            ..Default::default()
+        };
+
+        let daemons = find_nodes_on_daemons(&env.profile, &docker_args)
+            .await
+            .unwrap();
+        let statuses = query_nodes(&daemon_nodes(&daemons)).await;
+
+        // the container runs, but no axon answers on its port
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].name, "axon1");
+        assert_eq!(statuses[0].container, "running");
+        assert_eq!(statuses[0].height, None);
+        assert_eq!(statuses[0].peers, None);
+
+        let mut tracker = HeightTracker::default();
+        assert!(tracker.is_stalled(
+            &statuses[0].name,
+            statuses[0].height,
+            Duration::from_secs(15)
+        ));
+    }
+}
@@ -0,0 +1,301 @@
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use log::{error, info, warn};
+
+use super::{
+    health::{wait_for_nodes, NodeEndpoints},
+    nodes::{daemon_nodes, find_nodes_on_daemons, node_start_args, NodeContainer},
+    profile::Profile,
+};
+use crate::{
+    constants::{DEFAULT_PROFILE, HEALTH_CHECK_INTERVAL},
+    docker::DockerApi,
+    rpc::RpcClient,
+    types::{DockerArgs, Result},
+};
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct UpgradeArgs {
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// the new image of Axon, defaults to the one recorded in the profile
+    #[clap(long)]
+    image: Option<String>,
+
+    /// the new tag of Axon's image
+    #[clap(long)]
+    tag: String,
+
+    /// seconds to wait for each upgraded node to be ready and catch up
+    #[clap(short, long, default_value = "120")]
+    timeout: u64,
+
+    /// number of blocks an upgraded node may lag behind the others
+    #[clap(short, long, default_value = "2")]
+    lag_threshold: u64,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+async fn max_height(nodes: &[NodeEndpoints]) -> Option<u64> {
+    futures::future::join_all(nodes.iter().map(|node| async move {
+        RpcClient::new(&node.http_url)
+            .ok()?
+            .block_number()
+            .await
+            .ok()
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .max()
+}
+
+/// Wait until the node is no more than `lag_threshold` blocks behind the
+/// highest of the other nodes
+async fn wait_for_catch_up(
+    node: &NodeEndpoints,
+    others: &[NodeEndpoints],
+    lag_threshold: u64,
+    timeout: Duration,
+) -> Result<u64> {
+    let deadline = Instant::now() + timeout;
+    let rpc = RpcClient::new(&node.http_url)?;
+
+    loop {
+        let height = rpc.block_number().await.ok();
+        let cluster_height = max_height(others).await;
+
+        match (height, cluster_height) {
+            (Some(height), None) => return Ok(height),
+            (Some(height), Some(cluster_height)) if height + lag_threshold >= cluster_height => {
+                return Ok(height);
+            }
+            _ => {}
+        }
+
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "{} did not catch up in {}s, height: {:?}, cluster height: {:?}",
+                node.name,
+                timeout.as_secs(),
+                height,
+                cluster_height
+            )
+            .into());
+        }
+
+        tokio::time::sleep(Duration::from_secs(HEALTH_CHECK_INTERVAL)).await;
+    }
+}
+
+/// Split an image reference into the name and the tag, Docker takes `latest`
+/// if there is no tag
+fn split_image(image: &str) -> (String, String) {
+    let name_start = image.rfind('/').map_or(0, |pos| pos + 1);
+
+    match image[name_start..].rsplit_once(':') {
+        Some((name, tag)) => (format!("{}{name}", &image[..name_start]), tag.to_string()),
+        None => (image.to_string(), "latest".to_string()),
+    }
+}
+
+/// The profile with the image the container of a node actually runs, which
+/// differs from the recorded one if the profile was edited after the start
+async fn current_profile(
+    docker_api: &DockerApi,
+    profile: &Profile,
+    node: &NodeContainer,
+) -> Result<Profile> {
+    let image = docker_api
+        .find_container(&node.name)
+        .await?
+        .and_then(|container| container.config)
+        .and_then(|config| config.image)
+        .ok_or_else(|| format!("Image of container {} not found", node.name))?;
+    let (image, tag) = split_image(&image);
+
+    Ok(Profile {
+        image,
+        tag,
+        ..profile.clone()
+    })
+}
+
+/// Replace the container of a node with one running the image of the profile,
+/// the volumes and the config files are kept
+async fn replace_node(
+    docker_api: &DockerApi,
+    profile: &Profile,
+    node: &NodeContainer,
+) -> Result<()> {
+    let (port, p2p_port, collecting_port) =
+        match (node.http_port, node.p2p_port, node.collecting_port) {
+            (Some(port), Some(p2p_port), Some(collecting_port)) => {
+                (port, p2p_port, collecting_port)
+            }
+            _ => return Err(format!("Ports of container {} are not labeled", node.name).into()),
+        };
+
+    docker_api.stop_one_container(&node.name).await?;
+    docker_api.remove_one_container(&node.name, true).await?;
+    docker_api
+        .start_axon(node_start_args(
+            profile,
+            node.index,
+            port,
+            p2p_port,
+            collecting_port,
+            &node.resources,
+        ))
+        .await?;
+
+    Ok(())
+}
+
+async fn upgrade_node(
+    docker_api: &DockerApi,
+    profile: &Profile,
+    node: &NodeContainer,
+    endpoints: &[NodeEndpoints],
+    lag_threshold: u64,
+    timeout: Duration,
+) -> Result<()> {
+    replace_node(docker_api, profile, node).await?;
+
+    let (node_endpoints, others): (Vec<_>, Vec<_>) = endpoints
+        .iter()
+        .partition(|endpoints| endpoints.name == node.name);
+    let node_endpoints = match node_endpoints.first() {
+        Some(val) => *val,
+        None => return Err(format!("Endpoints of {} not found", node.name).into()),
+    };
+
+    let readiness = wait_for_nodes(docker_api, std::slice::from_ref(node_endpoints), timeout)
+        .await?
+        .remove(0);
+    if !readiness.is_ready() {
+        return Err(format!("{} failed the readiness check", node.name).into());
+    }
+
+    let others = others.into_iter().cloned().collect::<Vec<_>>();
+    let height = wait_for_catch_up(node_endpoints, &others, lag_threshold, timeout).await?;
+    info!("{} caught up at height {}", node.name, height);
+
+    Ok(())
+}
+
+pub async fn upgrade_nodes(args: UpgradeArgs) -> Result<()> {
+    let UpgradeArgs {
+        profile: profile_id,
+        image,
+        tag,
+        timeout,
+        lag_threshold,
+        docker_args,
+    } = args;
+
+    let timeout = Duration::from_secs(timeout);
+
+    let profile = Profile::load_or_default(&profile_id)?;
+    let new_profile = Profile {
+        image: image.unwrap_or_else(|| profile.image.clone()),
+        tag,
+        ..profile.clone()
+    };
+
+    let daemons = find_nodes_on_daemons(&profile_id, &docker_args).await?;
+    let nodes = daemon_nodes(&daemons);
+    if nodes.is_empty() {
+        return Ok(());
+    }
+    // the images to roll back to, inspected before any node is replaced
+    let mut old_profiles = Vec::new();
+    for (docker_api, node) in nodes.iter() {
+        old_profiles.push(current_profile(docker_api, &profile, node).await?);
+    }
+    let endpoints = nodes
+        .iter()
+        .map(|(_, node)| node.endpoints())
+        .collect::<Result<Vec<_>>>()?;
+
+    // pull the new image before touching any node to keep the downtime short
+    for (docker_api, _) in daemons.iter().filter(|(_, nodes)| !nodes.is_empty()) {
+        docker_api
+            .ensure_image(&new_profile.image, &new_profile.tag)
+            .await?;
+    }
+
+    let mut upgraded = Vec::new();
+    for ((docker_api, node), old_profile) in nodes.iter().zip(old_profiles) {
+        info!(
+            "Upgrading {} to {}:{}...",
+            node.name, new_profile.image, new_profile.tag
+        );
+        upgraded.push((*docker_api, *node, old_profile));
+
+        let result = upgrade_node(
+            docker_api,
+            &new_profile,
+            node,
+            &endpoints,
+            lag_threshold,
+            timeout,
+        )
+        .await;
+
+        if let Err(err) = result {
+            error!("Failed to upgrade {}: {}", node.name, err);
+            rollback(&upgraded, &endpoints, timeout).await;
+
+            return Err(format!(
+                "Upgrade to {}:{} aborted and rolled back",
+                new_profile.image, new_profile.tag
+            )
+            .into());
+        }
+    }
+
+    new_profile.save()?;
+    info!(
+        "All {} node(s) upgraded to {}:{}",
+        nodes.len(),
+        new_profile.image,
+        new_profile.tag
+    );
+
+    Ok(())
+}
+
+/// Put the upgraded nodes back on the images they ran, the latest upgraded
+/// first
+async fn rollback(
+    upgraded: &[(&DockerApi, &NodeContainer, Profile)],
+    endpoints: &[NodeEndpoints],
+    timeout: Duration,
+) {
+    for (docker_api, node, old_profile) in upgraded.iter().rev() {
+        warn!(
+            "Rolling back {} to {}:{}...",
+            node.name, old_profile.image, old_profile.tag
+        );
+
+        if let Err(err) = replace_node(docker_api, old_profile, node).await {
+            error!("Failed to roll back {}: {}", node.name, err);
+            continue;
+        }
+
+        let node_endpoints = endpoints
+            .iter()
+            .filter(|endpoints| endpoints.name == node.name)
+            .cloned()
+            .collect::<Vec<_>>();
+        if let Err(err) = wait_for_nodes(*docker_api, &node_endpoints, timeout).await {
+            error!("Failed to check {} after rolling back: {}", node.name, err);
+        }
+    }
+}
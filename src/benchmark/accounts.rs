@@ -0,0 +1,440 @@
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use axon_protocol::types::{Hex, TransactionAction, U256};
+use clap::{Args, Subcommand};
+use coins_bip32::{ecdsa::SigningKey, path::DerivationPath, xkeys::Parent};
+use coins_bip39::{English, Mnemonic};
+use log::{info, warn};
+use ophelia::PrivateKey;
+use ophelia_secp256k1::Secp256k1RecoverablePrivateKey;
+
+use super::{
+    config::read_devnet,
+    default_endpoint,
+    engine::{send_setup, Chain, Sender},
+};
+use crate::{
+    axon_nodes::profile::Profile,
+    constants::{
+        BENCHMARK_FUNDING, BENCHMARK_FUNDING_TIMEOUT, BENCHMARK_POLL_INTERVAL_MS, DEFAULT_PROFILE,
+        TRANSFER_GAS_LIMIT,
+    },
+    rpc::RpcClient,
+    types::Result,
+    utils::hex_decode,
+};
+
+/// the parent of the accounts derived from a mnemonic, as the nodes derive
+/// the accounts of the genesis
+const DERIVATION_PATH: &str = "m/44'/60'/0'/0";
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct PoolArgs {
+    /// the number of sender accounts
+    #[clap(long, default_value = "100")]
+    pub accounts: u32,
+
+    /// the private key of the account funding the senders, which takes back
+    /// what's left on them, defaults to the first account funded by the nodes
+    #[clap(long)]
+    funder_key: Option<String>,
+
+    /// derive the senders from a mnemonic rather than random keys, `devnet`
+    /// for the mnemonic of the nodes of the profile
+    #[clap(long)]
+    pub mnemonic: Option<String>,
+
+    /// the index of the first sender derived from the mnemonic, past the
+    /// accounts funded by the genesis
+    #[clap(long, default_value = "100")]
+    pub first_index: u32,
+}
+
+impl PoolArgs {
+    /// Replace `devnet` by the mnemonic of the nodes of a profile, and take
+    /// the first account the nodes derive from it as the funder if none is
+    /// given
+    pub fn resolve_devnet(&mut self, profile: &Profile) -> Result<()> {
+        let devnet_mnemonic = self.mnemonic.as_deref() == Some("devnet");
+        if !devnet_mnemonic && self.funder_key.is_some() {
+            return Ok(());
+        }
+
+        let devnet = read_devnet(profile)?;
+        if self.funder_key.is_none() {
+            let funder = derive_keys(&devnet.mnemonic, u32::try_from(devnet.mnemonic_index)?, 1)?;
+            self.funder_key = Some(Hex::encode(funder[0].to_bytes()).as_string());
+        }
+        if devnet_mnemonic {
+            self.mnemonic = Some(devnet.mnemonic);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Subcommand, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum AccountsActions {
+    /// Top up the accounts derived from a mnemonic, the one of the nodes by
+    /// default, from the funder
+    Fund(AccountsArgs),
+
+    /// Send what's left on the accounts back to the funder
+    Sweep(AccountsArgs),
+
+    /// List the accounts with their balances and nonces
+    List(AccountsArgs),
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct AccountsArgs {
+    #[clap(flatten)]
+    pool: PoolArgs,
+
+    /// the http endpoint of Axon, defaults to the first node of the profile
+    #[clap(short = 'H', long)]
+    http_endpoint: Option<String>,
+
+    /// the number of transactions sent in a JSON-RPC batch
+    #[clap(short, long, default_value = "100")]
+    batch_size: u32,
+
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+}
+
+/// The keys of `count` accounts derived from a mnemonic from `first`
+fn derive_keys(
+    phrase: &str,
+    first: u32,
+    count: u32,
+) -> Result<Vec<Secp256k1RecoverablePrivateKey>> {
+    // the seed takes thousands of rounds of hashing, so the parent is
+    // derived once for all the accounts
+    let parent = Mnemonic::<English>::new_from_phrase(phrase)?
+        .derive_key(&DerivationPath::from_str(DERIVATION_PATH)?, None)?;
+
+    (first..first.saturating_add(count))
+        .map(|index| {
+            let child = parent.derive_child(index)?;
+            let key: &SigningKey = child.as_ref();
+
+            Ok(Secp256k1RecoverablePrivateKey::try_from(
+                key.to_bytes().as_slice(),
+            )?)
+        })
+        .collect()
+}
+
+/// The account funding the senders and the senders, each with its nonce
+/// tracked locally
+pub struct AccountPool {
+    pub funder:  Sender,
+    pub senders: Vec<Sender>,
+}
+
+impl AccountPool {
+    /// Derive the senders from the mnemonic of the arguments if any, or
+    /// generate random ones
+    pub fn new(args: &PoolArgs) -> Result<Self> {
+        let PoolArgs {
+            accounts,
+            funder_key,
+            mnemonic,
+            first_index,
+        } = args;
+
+        let funder_key = funder_key
+            .as_deref()
+            .ok_or("No funder key given nor found in the devnet")?;
+        let funder = Sender::new(Secp256k1RecoverablePrivateKey::try_from(
+            hex_decode(funder_key)?.as_slice(),
+        )?)?;
+        let senders = match mnemonic {
+            Some(mnemonic) => derive_keys(mnemonic, *first_index, *accounts)?
+                .into_iter()
+                .map(Sender::new)
+                .collect::<Result<Vec<_>>>()?,
+            None => (0..*accounts)
+                .map(|_| Sender::random())
+                .collect::<Result<Vec<_>>>()?,
+        };
+
+        Ok(Self { funder, senders })
+    }
+
+    /// Sign the transfers of the funder topping up the senders below
+    /// `BENCHMARK_FUNDING`, a pool reused across runs only needs a little
+    pub async fn funding(
+        &mut self,
+        rpc: &RpcClient,
+        chain: &Chain,
+        batch_size: usize,
+    ) -> Result<Vec<String>> {
+        let funding = U256::from(BENCHMARK_FUNDING);
+        let mut txs = Vec::new();
+
+        for chunk in self.senders.chunks(batch_size) {
+            let addresses = chunk
+                .iter()
+                .map(|sender| sender.address)
+                .collect::<Vec<_>>();
+            let balances = rpc.balances(&addresses).await?;
+
+            for (address, balance) in addresses.into_iter().zip(balances) {
+                let balance = balance
+                    .map_err(|err| format!("Failed to get the balance of 0x{address:x}, {err}"))?;
+
+                if balance < funding {
+                    txs.push(
+                        self.funder
+                            .sign(
+                                TransactionAction::Call(address),
+                                funding - balance,
+                                Vec::new(),
+                                TRANSFER_GAS_LIMIT,
+                                chain,
+                            )
+                            .0,
+                    );
+                }
+            }
+        }
+
+        Ok(txs)
+    }
+
+    /// Send what's left on the senders but the fee back to the funder, and
+    /// wait for the transfers to be packed, returns the amount swept
+    pub async fn sweep(
+        &mut self,
+        rpc: &RpcClient,
+        chain: &Chain,
+        batch_size: usize,
+    ) -> Result<U256> {
+        let funder = self.funder.address;
+        let fee = chain.transfer_fee();
+        let mut swept = U256::zero();
+
+        for chunk in self.senders.chunks_mut(batch_size) {
+            // the nonces tracked during a run may be past what was packed
+            resync_nonces(rpc, chunk.iter_mut().collect()).await?;
+
+            let addresses = chunk
+                .iter()
+                .map(|sender| sender.address)
+                .collect::<Vec<_>>();
+            let balances = rpc.balances(&addresses).await?;
+            let mut transfers = Vec::new();
+            let mut txs = Vec::new();
+            for (index, (sender, balance)) in chunk.iter_mut().zip(balances).enumerate() {
+                let balance = balance.map_err(|err| {
+                    format!("Failed to get the balance of 0x{:x}, {err}", sender.address)
+                })?;
+                if balance <= fee {
+                    continue;
+                }
+
+                let (raw, _) = sender.sign(
+                    TransactionAction::Call(funder),
+                    balance - fee,
+                    Vec::new(),
+                    TRANSFER_GAS_LIMIT,
+                    chain,
+                );
+                transfers.push((index, balance - fee));
+                txs.push(raw);
+            }
+            if txs.is_empty() {
+                continue;
+            }
+
+            let responses = rpc.send_raw_transactions(&txs).await?;
+            for ((index, amount), response) in transfers.into_iter().zip(responses) {
+                let sender = &mut chunk[index];
+                match response {
+                    Ok(_) => swept += amount,
+                    Err(err) => {
+                        warn!("Failed to sweep 0x{:x}, {err}", sender.address);
+                        sender.nonce -= 1;
+                    }
+                }
+            }
+        }
+
+        wait_for_nonces(rpc, &self.senders, batch_size).await?;
+
+        Ok(swept)
+    }
+}
+
+/// Set the nonces of accounts to their transaction counts on chain, when the
+/// ones tracked locally may have gone wrong
+pub async fn resync_nonces(rpc: &RpcClient, senders: Vec<&mut Sender>) -> Result<()> {
+    let addresses = senders
+        .iter()
+        .map(|sender| sender.address)
+        .collect::<Vec<_>>();
+    let counts = rpc.transaction_counts(&addresses).await?;
+
+    for (sender, count) in senders.into_iter().zip(counts) {
+        sender.nonce = count
+            .map_err(|err| format!("Failed to get the nonce of 0x{:x}, {err}", sender.address))?;
+        sender.stale = false;
+    }
+
+    Ok(())
+}
+
+/// Wait for the transaction counts of accounts on chain to reach the nonces
+/// tracked locally, that is for all their transactions to be packed
+async fn wait_for_nonces(rpc: &RpcClient, senders: &[Sender], batch_size: usize) -> Result<()> {
+    let deadline = Instant::now() + Duration::from_secs(BENCHMARK_FUNDING_TIMEOUT);
+
+    for chunk in senders.chunks(batch_size) {
+        let addresses = chunk
+            .iter()
+            .map(|sender| sender.address)
+            .collect::<Vec<_>>();
+
+        loop {
+            let counts = rpc.transaction_counts(&addresses).await?;
+            if chunk
+                .iter()
+                .zip(counts)
+                .all(|(sender, count)| count.map_or(false, |count| count >= sender.nonce))
+            {
+                break;
+            }
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "Timed out waiting for the transactions of {} account(s)",
+                    senders.len()
+                )
+                .into());
+            }
+
+            tokio::time::sleep(Duration::from_millis(BENCHMARK_POLL_INTERVAL_MS)).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Top up the senders of a pool from its funder through `rpc`, and wait for
+/// the transfers to be packed
+pub async fn fund_pool(rpc: RpcClient, pool: &mut AccountPool, batch_size: usize) -> Result<()> {
+    let chain = Chain::new(&rpc).await?;
+    pool.funder.nonce = rpc.transaction_count(&pool.funder.address).await?;
+
+    let txs = pool.funding(&rpc, &chain, batch_size).await?;
+    if txs.is_empty() {
+        info!("All {} account(s) are funded", pool.senders.len());
+        return Ok(());
+    }
+
+    info!(
+        "Funding {} of {} account(s) from 0x{:x} at {}",
+        txs.len(),
+        pool.senders.len(),
+        pool.funder.address,
+        rpc.url()
+    );
+    send_setup(&[rpc], &pool.funder, &txs, batch_size).await
+}
+
+pub async fn exec_accounts(action: AccountsActions) -> Result<()> {
+    match action {
+        AccountsActions::Fund(args) => {
+            let (rpc, mut pool, batch_size) = open_pool(args)?;
+            fund_pool(rpc, &mut pool, batch_size).await
+        }
+        AccountsActions::Sweep(args) => {
+            let (rpc, mut pool, batch_size) = open_pool(args)?;
+            let chain = Chain::new(&rpc).await?;
+            let swept = pool.sweep(&rpc, &chain, batch_size).await?;
+            info!(
+                "Swept {swept} from {} account(s) back to 0x{:x}",
+                pool.senders.len(),
+                pool.funder.address
+            );
+
+            Ok(())
+        }
+        AccountsActions::List(args) => {
+            let (rpc, mut pool, batch_size) = open_pool(args)?;
+            list_accounts(&rpc, &mut pool, batch_size).await
+        }
+    }
+}
+
+/// The accounts of the mnemonic of the arguments, or of the nodes without it
+fn open_pool(args: AccountsArgs) -> Result<(RpcClient, AccountPool, usize)> {
+    let AccountsArgs {
+        mut pool,
+        http_endpoint,
+        batch_size,
+        profile,
+    } = args;
+
+    let axon_profile = Profile::load_or_default(&profile)?;
+    pool.mnemonic.get_or_insert_with(|| "devnet".to_string());
+    pool.resolve_devnet(&axon_profile)?;
+    if batch_size == 0 {
+        return Err("The batch size must be positive".into());
+    }
+
+    Ok((
+        RpcClient::new(http_endpoint.unwrap_or_else(|| default_endpoint(&axon_profile)))?,
+        AccountPool::new(&pool)?,
+        usize::try_from(batch_size)?,
+    ))
+}
+
+async fn list_accounts(rpc: &RpcClient, pool: &mut AccountPool, batch_size: usize) -> Result<()> {
+    for chunk in pool.senders.chunks_mut(batch_size) {
+        resync_nonces(rpc, chunk.iter_mut().collect()).await?;
+
+        let addresses = chunk
+            .iter()
+            .map(|sender| sender.address)
+            .collect::<Vec<_>>();
+        for (sender, balance) in chunk.iter().zip(rpc.balances(&addresses).await?) {
+            match balance {
+                Ok(balance) => println!(
+                    "0x{:x} balance: {balance}, nonce: {}",
+                    sender.address, sender.nonce
+                ),
+                Err(err) => warn!("Failed to get the balance of 0x{:x}, {err}", sender.address),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_keys_as_the_devnet() {
+        let keys = derive_keys(
+            "test test test test test test test test test test test junk",
+            0,
+            2,
+        )
+        .unwrap();
+
+        assert_eq!(keys.len(), 2);
+        assert_eq!(
+            Hex::encode(keys[0].to_bytes()).as_string(),
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcac78d7c2a3f2ff80"
+        );
+        assert_ne!(keys[0].to_bytes(), keys[1].to_bytes());
+    }
+}
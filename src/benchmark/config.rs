@@ -0,0 +1,182 @@
+use std::{
+    fs::{create_dir_all, read_to_string},
+    path::Path,
+};
+
+use axon_protocol::types::RichBlock;
+use log::{info, warn};
+use serde_json::{json, Map, Value};
+
+use super::engine::LoadArgs;
+use crate::{
+    axon_nodes::profile::Profile,
+    rpc::RpcClient,
+    types::Result,
+    utils::{find_toml_value, from_json_file, to_json_file},
+};
+
+/// The settings of the devnet the container benchmark has to agree with
+pub struct Devnet {
+    pub chain_id:       u64,
+    pub mnemonic:       String,
+    pub mnemonic_index: u64,
+}
+
+/// Read the chain id from `genesis.json` and the mnemonic from the
+/// `[accounts]` section of the config of the first node
+pub fn read_devnet(profile: &Profile) -> Result<Devnet> {
+    let nodes_path = Path::new(&profile.path).join("nodes");
+    let genesis: RichBlock = from_json_file(nodes_path.join("genesis.json"))?;
+    let config = read_to_string(nodes_path.join("config_1.toml"))?;
+
+    Ok(Devnet {
+        chain_id:       genesis.block.header.chain_id,
+        mnemonic:       find_toml_value(&config, "mnemonic")
+            .ok_or("No mnemonic found in the [accounts] of the node config")?
+            .to_string(),
+        mnemonic_index: find_toml_value(&config, "initial_index").map_or(Ok(0), str::parse)?,
+    })
+}
+
+/// The fields of `config.json` not derived from the devnet, taken from the
+/// command line when the file is generated
+fn default_config(load_args: &LoadArgs) -> Map<String, Value> {
+    let LoadArgs {
+        concurrency,
+        batch_size,
+        duration,
+        ..
+    } = load_args;
+
+    json!({
+        "continuous_benchmark": false,
+        "benchmark_time": duration * 1000,
+        "batch_size": batch_size,
+        "thread_num": concurrency,
+        "id": "",
+        "token": "",
+        "benchmark_cases": ["./benchmark", "./contract_benchmark"],
+    })
+    .as_object()
+    .cloned()
+    .unwrap_or_default()
+}
+
+fn validate_config(config: &Map<String, Value>) -> Result<()> {
+    let positive = |key: &str| config.get(key).and_then(Value::as_u64).unwrap_or(0) > 0;
+
+    let endpoint = config
+        .get("http_endpoint")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+        return Err(format!("Invalid http_endpoint \"{endpoint}\" in config.json").into());
+    }
+
+    let words = config
+        .get("mnemonic")
+        .and_then(Value::as_str)
+        .map_or(0, |mnemonic| mnemonic.split_whitespace().count());
+    if ![12, 15, 18, 21, 24].contains(&words) {
+        return Err(format!("Invalid mnemonic of {words} word(s) in config.json").into());
+    }
+
+    for key in ["chain_id", "batch_size", "thread_num"] {
+        if !positive(key) {
+            return Err(format!("{key} in config.json must be a positive integer").into());
+        }
+    }
+    if !config
+        .get("continuous_benchmark")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+        && !positive("benchmark_time")
+    {
+        return Err("benchmark_time in config.json must be a positive integer".into());
+    }
+
+    if config
+        .get("benchmark_cases")
+        .and_then(Value::as_array)
+        .map_or(true, |cases| cases.is_empty())
+    {
+        return Err("No benchmark_cases in config.json".into());
+    }
+
+    Ok(())
+}
+
+/// Generate `config.json` of the container benchmark under `path` from the
+/// devnet of a profile, or patch the chain id, mnemonic and endpoint of an
+/// existing one, validating it before it's written
+///
+/// With `keep`, an existing file is only validated. The mnemonic and index
+/// are the ones of the account pool if any. The chain id is also checked
+/// against a node through its published port if it's reachable.
+pub async fn prepare_config(
+    path: &Path,
+    profile: &Profile,
+    http_endpoint: &str,
+    load_args: &LoadArgs,
+    keep: bool,
+) -> Result<()> {
+    let config_path = path.join("config.json");
+    let exists = config_path.exists();
+    let mut config: Map<String, Value> = if exists {
+        from_json_file(&config_path)?
+    } else {
+        default_config(load_args)
+    };
+
+    if !(keep && exists) {
+        let Devnet {
+            chain_id,
+            mut mnemonic,
+            mut mnemonic_index,
+        } = read_devnet(profile)?;
+
+        // the accounts of a pool are funded already, so the benchmark sends
+        // from them rather than from the ones of the genesis
+        if let Some(pool_mnemonic) = &load_args.pool.mnemonic {
+            mnemonic = pool_mnemonic.clone();
+            mnemonic_index = u64::from(load_args.pool.first_index);
+        }
+
+        config.insert("http_endpoint".to_string(), json!(http_endpoint));
+        config.insert("chain_id".to_string(), json!(chain_id));
+        config.insert("mnemonic".to_string(), json!(mnemonic));
+        config.insert("mnemonic_index".to_string(), json!(mnemonic_index));
+        validate_config(&config)?;
+
+        create_dir_all(path)?;
+        to_json_file(&config, &config_path)?;
+        info!(
+            "Benchmark config {} {}",
+            config_path.display(),
+            if exists { "patched" } else { "generated" }
+        );
+    } else {
+        validate_config(&config)?;
+    }
+
+    let ports = match profile.ports.first() {
+        Some(ports) => ports,
+        None => return Ok(()),
+    };
+    let rpc = RpcClient::new(format!(
+        "http://{}:{}",
+        profile.node_address(ports.index),
+        ports.http
+    ))?;
+    match rpc.chain_id().await {
+        Ok(chain_id) if Some(chain_id) != config.get("chain_id").and_then(Value::as_u64) => {
+            return Err(
+                format!("chain_id in config.json doesn't match {chain_id} of the nodes").into(),
+            );
+        }
+        Ok(_) => {}
+        Err(err) => warn!("Skip checking chain_id against the nodes, {err}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,820 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use axon_protocol::types::{
+    Eip1559Transaction, Hex, SignedTransaction, TransactionAction, UnsignedTransaction, H160, H256,
+    U256,
+};
+use clap::{ArgEnum, Args};
+use futures::{future::join_all, stream::FuturesUnordered, StreamExt};
+use log::{info, warn};
+use ophelia::{PrivateKey, PublicKey};
+use ophelia_secp256k1::Secp256k1RecoverablePrivateKey;
+use rand::rngs::OsRng;
+
+use super::{
+    accounts::{resync_nonces, AccountPool, PoolArgs},
+    rate::{RateProfile, RateSchedule},
+    report::BlockStats,
+    scenario::{prepare_mix, Mix, ScenarioKind, WeightedScenario},
+    series::{delete_run, push_point, Series, SeriesPoint},
+};
+use crate::{
+    axon_nodes::config::sign_tx,
+    constants::{
+        BENCHMARK_FUNDING_TIMEOUT, BENCHMARK_INCLUSION_TIMEOUT, BENCHMARK_POLL_INTERVAL_MS,
+        TRANSFER_GAS_LIMIT,
+    },
+    rpc::RpcClient,
+    types::Result,
+    utils::pubkey_address,
+};
+
+/// How the workers of the native engine are spread across the endpoints
+#[derive(ArgEnum, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Distribution {
+    /// the endpoints take the workers in turn
+    RoundRobin,
+    /// the endpoints take workers in proportion to `--weights`
+    Weighted,
+}
+
+impl Default for Distribution {
+    fn default() -> Self {
+        Self::RoundRobin
+    }
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct LoadArgs {
+    /// the number of workers sending transactions concurrently
+    #[clap(short, long, default_value = "10")]
+    pub concurrency: u32,
+
+    /// the number of transactions sent in a JSON-RPC batch
+    #[clap(short, long, default_value = "100")]
+    pub batch_size: u32,
+
+    /// how long to send transactions, in seconds
+    #[clap(short = 't', long, default_value = "60")]
+    pub duration: u64,
+
+    #[clap(flatten)]
+    pub pool: PoolArgs,
+
+    /// leave what's left on the senders after the run rather than sweeping it
+    /// back to the funder
+    #[clap(long)]
+    pub keep_accounts: bool,
+
+    /// the scenario of the load without a scenario file
+    #[clap(short, long, arg_enum, default_value = "transfer")]
+    pub scenario: ScenarioKind,
+
+    /// a JSON array of scenarios mixed by weight, e.g.
+    /// `[{"kind": "transfer", "weight": 3}, {"kind": "storage", "slots": 20}]`
+    #[clap(long)]
+    pub scenario_file: Option<String>,
+
+    /// how the workers are spread across the endpoints
+    #[clap(long, arg_enum, default_value = "round-robin")]
+    pub distribution: Distribution,
+
+    /// the weights of the endpoints in the weighted distribution seperated by
+    /// ',', e.g. 3,1
+    #[clap(long, value_delimiter = ',')]
+    weights: Vec<u32>,
+
+    /// send this many transactions per second whatever the node responds,
+    /// rather than each worker sending a batch right after another
+    #[clap(long)]
+    pub rate: Option<u32>,
+
+    /// how the rate changes over the run
+    #[clap(long, arg_enum, default_value = "constant")]
+    pub rate_profile: RateProfile,
+
+    /// the rate at the end of a linear or step profile
+    #[clap(long)]
+    pub rate_to: Option<u32>,
+
+    /// the number of steps of a step profile
+    #[clap(long, default_value = "5")]
+    pub steps: u32,
+
+    /// the Prometheus pushgateway to push the live metrics to, e.g.
+    /// http://127.0.0.1:9091
+    #[clap(long)]
+    pub pushgateway: Option<String>,
+}
+
+/// What a run of the native engine measured
+#[derive(Debug, Default)]
+pub struct LoadResult {
+    pub sent:                usize,
+    /// the transactions refused by the node
+    pub rejected:            usize,
+    /// the transactions of the batches that didn't reach the node
+    pub failed:              usize,
+    /// the time spent sending transactions
+    pub elapsed:             Duration,
+    /// the round trips of the batches carrying the accepted transactions
+    pub submit_latencies:    Vec<Duration>,
+    /// the transactions accepted by the endpoints
+    pub accepted_hashes:     Vec<H256>,
+    /// from submitting a transaction to seeing it in a block
+    pub inclusion_latencies: Vec<Duration>,
+    /// the blocks produced while the load was running
+    pub blocks:              Vec<BlockStats>,
+    pub last_error:          Option<String>,
+    /// the transactions sent to each endpoint
+    pub endpoints:           Vec<EndpointResult>,
+    /// the run second by second
+    pub series:              Vec<SeriesPoint>,
+}
+
+/// What a run measured of the transactions sent to an endpoint
+#[derive(Debug, Default)]
+pub struct EndpointResult {
+    pub endpoint:            String,
+    pub sent:                usize,
+    pub accepted:            usize,
+    pub rejected:            usize,
+    pub failed:              usize,
+    pub inclusion_latencies: Vec<Duration>,
+}
+
+impl LoadResult {
+    /// Add the result of a worker sending to the `endpoint`th endpoint
+    fn merge(&mut self, endpoint: usize, other: LoadResult) {
+        let stats = &mut self.endpoints[endpoint];
+        stats.sent += other.sent;
+        stats.accepted += other.submit_latencies.len();
+        stats.rejected += other.rejected;
+        stats.failed += other.failed;
+
+        self.sent += other.sent;
+        self.rejected += other.rejected;
+        self.failed += other.failed;
+        self.submit_latencies.extend(other.submit_latencies);
+        self.accepted_hashes.extend(other.accepted_hashes);
+        if other.last_error.is_some() {
+            self.last_error = other.last_error;
+        }
+    }
+}
+
+/// An account sending transactions with its nonce tracked locally
+pub struct Sender {
+    key:         Secp256k1RecoverablePrivateKey,
+    pub address: H160,
+    pub nonce:   u64,
+    /// a transaction was rejected, so the nonce is to be resynced from the
+    /// chain
+    pub stale:   bool,
+}
+
+impl Sender {
+    pub fn new(key: Secp256k1RecoverablePrivateKey) -> Result<Self> {
+        let address = pubkey_address(key.pub_key().to_bytes())?;
+
+        Ok(Self {
+            key,
+            address: H160::from_slice(address.as_slice()),
+            nonce: 0,
+            stale: false,
+        })
+    }
+
+    /// Send `nonce` again, as later nonces can't be packed after a gap, unless
+    /// the chain tells otherwise when a node refused it
+    pub fn rewind(&mut self, nonce: u64, refused: bool) {
+        self.nonce = self.nonce.min(nonce);
+        self.stale |= refused;
+    }
+
+    pub fn random() -> Result<Self> {
+        Self::new(Secp256k1RecoverablePrivateKey::generate(&mut OsRng))
+    }
+
+    /// Sign a transaction with the next nonce, returns the raw transaction and
+    /// its hash
+    pub fn sign(
+        &mut self,
+        action: TransactionAction,
+        value: U256,
+        data: Vec<u8>,
+        gas_limit: u64,
+        chain: &Chain,
+    ) -> (String, H256) {
+        let tx = UnsignedTransaction::Eip1559(Eip1559Transaction {
+            nonce: self.nonce.into(),
+            max_priority_fee_per_gas: chain.gas_price,
+            gas_price: chain.gas_price,
+            gas_limit: gas_limit.into(),
+            action,
+            value,
+            data: data.into(),
+            access_list: Default::default(),
+        });
+        self.nonce += 1;
+
+        let SignedTransaction { transaction, .. } = sign_tx(&self.key, tx, chain.id);
+        let raw = transaction
+            .unsigned
+            .encode(transaction.chain_id, transaction.signature.clone());
+
+        (Hex::encode(raw).as_string(), transaction.hash)
+    }
+}
+
+/// The transactions accepted by the endpoints and not yet seen in a block,
+/// with when and where they were submitted
+#[derive(Default)]
+struct PendingTxs(Mutex<HashMap<H256, (Instant, usize)>>);
+
+impl PendingTxs {
+    fn insert(&self, hash: H256, submitted: Instant, endpoint: usize) {
+        self.0.lock().unwrap().insert(hash, (submitted, endpoint));
+    }
+
+    /// Remove the transactions of a block, returns their endpoints and
+    /// inclusion latencies
+    fn take_included(&self, hashes: &[H256]) -> Vec<(usize, Duration)> {
+        let now = Instant::now();
+        let mut pending = self.0.lock().unwrap();
+
+        hashes
+            .iter()
+            .filter_map(|hash| pending.remove(hash))
+            .map(|(submitted, endpoint)| (endpoint, now.duration_since(submitted)))
+            .collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().is_empty()
+    }
+}
+
+pub struct Chain {
+    id:        u64,
+    gas_price: U256,
+}
+
+impl Chain {
+    pub async fn new(rpc: &RpcClient) -> Result<Self> {
+        Ok(Self {
+            id:        rpc.chain_id().await?,
+            gas_price: rpc.gas_price().await?,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn with_gas_price(id: u64, gas_price: U256) -> Self {
+        Self { id, gas_price }
+    }
+
+    /// The most a plain transfer costs
+    pub fn transfer_fee(&self) -> U256 {
+        self.gas_price * U256::from(TRANSFER_GAS_LIMIT)
+    }
+}
+
+/// What the workers share while sending the mix
+struct Workload<'a> {
+    chain:      &'a Chain,
+    mix:        &'a Mix,
+    batch_size: usize,
+    started:    Instant,
+    deadline:   Instant,
+    /// the schedule of an open-loop run
+    rate:       Option<&'a RateSchedule>,
+    pending:    &'a PendingTxs,
+    series:     &'a Series,
+}
+
+impl Workload<'_> {
+    /// When to send the next batch, right away in a closed loop, or none at
+    /// the end of the run
+    fn next_due(&self) -> Option<Instant> {
+        match self.rate {
+            Some(rate) => rate.claim(self.batch_size).map(|due| self.started + due),
+            None => Some(Instant::now()).filter(|now| *now < self.deadline),
+        }
+    }
+}
+
+/// A batch sent to an endpoint with its responses, where each transaction has
+/// the index of its sender, its nonce and hash
+struct Submitted {
+    txs:       Vec<(usize, u64, H256)>,
+    at:        Instant,
+    latency:   Duration,
+    responses: std::result::Result<Vec<std::result::Result<H256, String>>, String>,
+}
+
+async fn submit(rpc: &RpcClient, txs: Vec<(usize, u64, H256)>, raws: Vec<String>) -> Submitted {
+    let at = Instant::now();
+    let responses = rpc
+        .send_raw_transactions(&raws)
+        .await
+        .map_err(|err| err.to_string());
+
+    Submitted {
+        txs,
+        at,
+        latency: at.elapsed(),
+        responses,
+    }
+}
+
+/// The endpoint of each worker, taken in turn or in proportion to the weights
+fn assign_endpoints(args: &LoadArgs, endpoints: usize, workers: usize) -> Result<Vec<usize>> {
+    let schedule = match args.distribution {
+        Distribution::RoundRobin => (0..endpoints).collect::<Vec<_>>(),
+        Distribution::Weighted => {
+            if args.weights.len() != endpoints {
+                return Err(format!(
+                    "{} weight(s) given for {endpoints} endpoint(s)",
+                    args.weights.len()
+                )
+                .into());
+            }
+
+            args.weights
+                .iter()
+                .enumerate()
+                .flat_map(|(index, weight)| (0..*weight).map(move |_| index))
+                .collect()
+        }
+    };
+
+    if schedule.is_empty() {
+        return Err("No endpoint with a positive weight".into());
+    }
+    if workers < endpoints {
+        warn!("Only {workers} worker(s) for {endpoints} endpoint(s), some get no load");
+    }
+
+    Ok((0..workers)
+        .map(|worker| schedule[worker % schedule.len()])
+        .collect())
+}
+
+/// Send the transactions of the funder in batches through the first endpoint,
+/// and wait for all of them to be packed as seen by every endpoint
+pub async fn send_setup(
+    rpcs: &[RpcClient],
+    funder: &Sender,
+    txs: &[String],
+    batch_size: usize,
+) -> Result<()> {
+    let rpc = &rpcs[0];
+    for chunk in txs.chunks(batch_size) {
+        if let Some(Err(err)) = rpc
+            .send_raw_transactions(chunk)
+            .await?
+            .into_iter()
+            .find(|response| response.is_err())
+        {
+            return Err(format!("Failed to set up the benchmark, {err}").into());
+        }
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(BENCHMARK_FUNDING_TIMEOUT);
+    for rpc in rpcs {
+        while rpc.transaction_count(&funder.address).await? < funder.nonce {
+            if Instant::now() >= deadline {
+                return Err("Timed out waiting for the setup transactions".into());
+            }
+
+            tokio::time::sleep(Duration::from_millis(BENCHMARK_POLL_INTERVAL_MS)).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Count the responses of a batch, returns whether it didn't reach the node
+fn record(
+    submitted: Submitted,
+    endpoint: usize,
+    senders: &mut [Sender],
+    load: &Workload<'_>,
+    result: &mut LoadResult,
+) -> bool {
+    let Submitted {
+        txs,
+        at,
+        latency,
+        responses,
+    } = submitted;
+
+    let failed = responses.is_err();
+    let responses = match responses {
+        Ok(responses) => responses,
+        Err(err) => {
+            warn!("Failed to send a batch of transactions: {err}");
+            result.failed += txs.len();
+            txs.iter().map(|_| Err(err.clone())).collect()
+        }
+    };
+
+    let (mut accepted, mut rejected) = (0, 0);
+    for ((index, nonce, hash), response) in txs.iter().zip(responses) {
+        match response {
+            Ok(_) => {
+                load.pending.insert(*hash, at, endpoint);
+                result.submit_latencies.push(latency);
+                result.accepted_hashes.push(*hash);
+                accepted += 1;
+            }
+            Err(err) => {
+                senders[*index].rewind(*nonce, !failed);
+                if !failed {
+                    rejected += 1;
+                }
+                result.last_error = Some(err);
+            }
+        }
+    }
+    result.rejected += rejected;
+    load.series.responded(
+        at,
+        accepted,
+        rejected,
+        if failed { txs.len() } else { 0 },
+        latency,
+    );
+
+    failed
+}
+
+/// Send batches of the mix from the senders in turn to the `endpoint`th
+/// endpoint, one after another in a closed loop, or when they are due
+/// whatever the responses in an open loop
+async fn send_mix(
+    rpc: &RpcClient,
+    endpoint: usize,
+    senders: &mut [Sender],
+    load: &Workload<'_>,
+) -> LoadResult {
+    let mut result = LoadResult::default();
+    let mut in_flight = FuturesUnordered::new();
+    let mut next = 0;
+
+    while let Some(due) = load.next_due() {
+        // a closed loop waits for the previous batch, an open one only
+        // handles the responses arrived meanwhile
+        loop {
+            tokio::select! {
+                Some(submitted) = in_flight.next() => {
+                    let failed = record(submitted, endpoint, senders, load, &mut result);
+
+                    // don't spin against a node that is down
+                    if failed && load.rate.is_none() {
+                        tokio::time::sleep(Duration::from_millis(BENCHMARK_POLL_INTERVAL_MS))
+                            .await;
+                    }
+                }
+                _ = tokio::time::sleep_until(due.into()),
+                    if load.rate.is_some() || in_flight.is_empty() => break,
+            }
+        }
+
+        if senders.iter().any(|sender| sender.stale) {
+            if let Err(err) = resync_nonces(
+                rpc,
+                senders.iter_mut().filter(|sender| sender.stale).collect(),
+            )
+            .await
+            {
+                warn!("Failed to resync the nonces of the senders: {err}");
+            }
+        }
+
+        let (txs, raws): (Vec<_>, Vec<_>) = (0..load.batch_size)
+            .map(|_| {
+                let index = next % senders.len();
+                let sender = &mut senders[index];
+                let nonce = sender.nonce;
+                let (raw, hash) = load.mix.sign(next, sender, load.chain);
+                next += 1;
+
+                ((index, nonce, hash), raw)
+            })
+            .unzip();
+
+        result.sent += txs.len();
+        load.series.offered(Instant::now(), txs.len());
+        in_flight.push(submit(rpc, txs, raws));
+    }
+
+    while let Some(submitted) = in_flight.next().await {
+        record(submitted, endpoint, senders, load, &mut result);
+    }
+
+    result
+}
+
+/// Follow new blocks to measure the inclusion latencies, until all senders
+/// stop and the pending transactions are packed or time out
+async fn watch_blocks(
+    rpc: &RpcClient,
+    from: u64,
+    pending: &PendingTxs,
+    series: &Series,
+    active: &AtomicUsize,
+) -> (Vec<(usize, Duration)>, Vec<BlockStats>) {
+    let mut latencies = Vec::new();
+    let mut blocks = Vec::new();
+    let mut next = from + 1;
+    let mut drain_deadline = None;
+
+    loop {
+        if let Ok(height) = rpc.block_number().await {
+            while next <= height {
+                match rpc.block_summary(next).await {
+                    Ok(block) => {
+                        let included = pending.take_included(&block.transactions);
+                        series
+                            .included(Instant::now(), included.iter().map(|(_, latency)| *latency));
+                        latencies.extend(included);
+                        blocks.push(BlockStats {
+                            number:    next,
+                            timestamp: block.timestamp.as_u64(),
+                            tx_count:  block.transactions.len(),
+                            gas_used:  block.gas_used.as_u64(),
+                        });
+                    }
+                    Err(err) => {
+                        warn!("Failed to get block {next}: {err}");
+                        break;
+                    }
+                }
+                next += 1;
+            }
+        }
+
+        if active.load(Ordering::SeqCst) == 0 {
+            let drain_deadline = *drain_deadline.get_or_insert_with(|| {
+                Instant::now() + Duration::from_secs(BENCHMARK_INCLUSION_TIMEOUT)
+            });
+
+            if pending.is_empty() || Instant::now() >= drain_deadline {
+                return (latencies, blocks);
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(BENCHMARK_POLL_INTERVAL_MS)).await;
+    }
+}
+
+/// Log the last second of the series while the workers are sending, and push
+/// it to the pushgateway if any, until the run ends and its metrics are
+/// deleted
+async fn follow_series(
+    series: &Series,
+    rate: Option<&RateSchedule>,
+    active: &AtomicUsize,
+    pushgateway: Option<&str>,
+    run_id: &str,
+) {
+    let client = reqwest::Client::new();
+    let mut second = 0;
+
+    while active.load(Ordering::SeqCst) > 0 {
+        tokio::time::sleep_until(series.closes_at(second).into()).await;
+
+        let point = series.point(second, rate);
+        info!(
+            "{}s: {} offered, {} accepted, {} achieved TPS{}",
+            second + 1,
+            point.offered_tps,
+            point.accepted_tps,
+            point.achieved_tps,
+            point
+                .target_tps
+                .map_or_else(String::new, |target| format!(", target {target:.0} TPS"))
+        );
+
+        if let Some(pushgateway) = pushgateway {
+            if let Err(err) = push_point(&client, pushgateway, run_id, &point).await {
+                warn!("Failed to push the metrics to {pushgateway}: {err}");
+            }
+        }
+
+        second += 1;
+    }
+
+    if let Some(pushgateway) = pushgateway {
+        if let Err(err) = delete_run(&client, pushgateway, run_id).await {
+            warn!("Failed to delete the metrics from {pushgateway}: {err}");
+        }
+    }
+}
+
+/// Top up the senders of the pool and set up the scenarios, then fire the mix
+/// of scenarios at the endpoints and measure how long the transactions take
+/// to be accepted and packed, and sweep the senders back to the funder
+///
+/// The metrics pushed to the pushgateway are grouped by `run_id`.
+pub async fn run_load(
+    http_endpoints: &[String],
+    args: &LoadArgs,
+    scenarios: &[WeightedScenario],
+    run_id: &str,
+) -> Result<LoadResult> {
+    let LoadArgs {
+        pool: PoolArgs { accounts, .. },
+        concurrency,
+        batch_size,
+        duration,
+        keep_accounts,
+        pushgateway,
+        ..
+    } = args;
+
+    if *accounts == 0 || *concurrency == 0 || *batch_size == 0 {
+        return Err("The accounts, concurrency and batch size must be positive".into());
+    }
+    if http_endpoints.is_empty() {
+        return Err("No http endpoint to send transactions to".into());
+    }
+    let batch_size = usize::try_from(*batch_size)?;
+    let concurrency = usize::try_from(*concurrency)?;
+    let rate = RateSchedule::new(args)?;
+
+    // each worker takes its own senders, so nonces are never shared
+    let per_worker = (usize::try_from(*accounts)? + concurrency - 1) / concurrency;
+    let assigned = assign_endpoints(
+        args,
+        http_endpoints.len(),
+        (usize::try_from(*accounts)? + per_worker - 1) / per_worker,
+    )?;
+
+    let rpcs = http_endpoints
+        .iter()
+        .map(RpcClient::new)
+        .collect::<Result<Vec<_>>>()?;
+    let chain = Chain::new(&rpcs[0]).await?;
+    for (rpc, endpoint) in rpcs.iter().zip(http_endpoints).skip(1) {
+        let chain_id = rpc.chain_id().await?;
+        if chain_id != chain.id {
+            return Err(format!(
+                "{endpoint} is on chain {chain_id} rather than {} of {}",
+                chain.id, http_endpoints[0]
+            )
+            .into());
+        }
+    }
+
+    let mut pool = AccountPool::new(&args.pool)?;
+    pool.funder.nonce = rpcs[0].transaction_count(&pool.funder.address).await?;
+    let mut setup = pool.funding(&rpcs[0], &chain, batch_size).await?;
+    let funded = setup.len();
+    let AccountPool { funder, senders } = &mut pool;
+    let (scenario_setup, mix) = prepare_mix(scenarios, funder, senders, &chain)?;
+    setup.extend(scenario_setup);
+
+    info!(
+        "Funding {funded} of {accounts} sender(s) and setting up the scenarios from 0x{:x} at {}",
+        funder.address, http_endpoints[0]
+    );
+    send_setup(&rpcs, funder, &setup, batch_size).await?;
+
+    let workers = senders.chunks_mut(per_worker).collect::<Vec<_>>();
+    let active = AtomicUsize::new(workers.len());
+    let pending = PendingTxs::default();
+
+    info!(
+        "Sending transactions for {duration}s at {} with {} worker(s) in batches of \
+         {batch_size} to {} endpoint(s)",
+        rate.as_ref()
+            .map_or_else(|| "full speed".to_string(), |rate| rate.to_string()),
+        workers.len(),
+        http_endpoints.len()
+    );
+    let from = rpcs[0].block_number().await?;
+    let started = Instant::now();
+    let series = Series::new(started);
+    let load = Workload {
+        chain: &chain,
+        mix: &mix,
+        batch_size,
+        started,
+        deadline: started + Duration::from_secs(*duration),
+        rate: rate.as_ref(),
+        pending: &pending,
+        series: &series,
+    };
+
+    let (rpcs, load, active) = (&rpcs, &load, &active);
+    let (results, (included, blocks), _) = futures::join!(
+        join_all(
+            workers
+                .into_iter()
+                .zip(assigned)
+                .map(|(senders, endpoint)| async move {
+                    let result = send_mix(&rpcs[endpoint], endpoint, senders, load).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+
+                    (endpoint, result)
+                })
+        ),
+        watch_blocks(&rpcs[0], from, &pending, &series, active),
+        follow_series(
+            &series,
+            rate.as_ref(),
+            active,
+            pushgateway.as_deref(),
+            run_id
+        ),
+    );
+
+    let mut result = LoadResult {
+        elapsed: started.elapsed().min(Duration::from_secs(*duration)),
+        blocks,
+        series: series.points(rate.as_ref()),
+        endpoints: http_endpoints
+            .iter()
+            .map(|endpoint| EndpointResult {
+                endpoint: endpoint.clone(),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    for (endpoint, worker_result) in results {
+        result.merge(endpoint, worker_result);
+    }
+    for (endpoint, latency) in included {
+        result.endpoints[endpoint].inclusion_latencies.push(latency);
+        result.inclusion_latencies.push(latency);
+    }
+
+    if !keep_accounts {
+        // the run is measured already, so what's left is only worth a warning
+        match pool.sweep(&rpcs[0], &chain, batch_size).await {
+            Ok(swept) => info!("Swept {swept} back to 0x{:x}", pool.funder.address),
+            Err(err) => warn!("Failed to sweep the senders back to the funder, {err}"),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weighted(weights: &[u32]) -> LoadArgs {
+        LoadArgs {
+            distribution: Distribution::Weighted,
+            weights: weights.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn assign_endpoints_in_turn() {
+        let args = LoadArgs::default();
+
+        assert_eq!(assign_endpoints(&args, 3, 5).unwrap(), [0, 1, 2, 0, 1]);
+        assert_eq!(assign_endpoints(&args, 3, 2).unwrap(), [0, 1]);
+    }
+
+    #[test]
+    fn assign_endpoints_by_weight() {
+        assert_eq!(assign_endpoints(&weighted(&[2, 1]), 2, 6).unwrap(), [
+            0, 0, 1, 0, 0, 1
+        ]);
+        assert_eq!(assign_endpoints(&weighted(&[0, 1]), 2, 3).unwrap(), [
+            1, 1, 1
+        ]);
+
+        assert!(assign_endpoints(&weighted(&[1]), 2, 4).is_err());
+        assert!(assign_endpoints(&weighted(&[0, 0]), 2, 4).is_err());
+    }
+
+    #[test]
+    fn rewind_keeps_the_lowest_nonce() {
+        let mut sender = Sender::random().unwrap();
+        sender.nonce = 10;
+
+        sender.rewind(7, false);
+        sender.rewind(8, false);
+        assert_eq!(sender.nonce, 7);
+        assert!(!sender.stale);
+
+        sender.rewind(9, true);
+        assert_eq!(sender.nonce, 7);
+        assert!(sender.stale);
+    }
+}
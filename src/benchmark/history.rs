@@ -0,0 +1,151 @@
+use std::{fs::read_dir, path::Path};
+
+use clap::Args;
+use colored::*;
+use log::{info, warn};
+
+use super::report::RunReport;
+use crate::{constants::DEFAULT_BENCHMARK_RUNS_PATH, types::Result};
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct CompareArgs {
+    /// the id of the baseline run
+    baseline: String,
+
+    /// the id of the run to check against the baseline
+    current: String,
+
+    /// the largest drop of TPS in percent not taken as a regression
+    #[clap(long, default_value = "10")]
+    max_tps_drop: u32,
+
+    /// the largest increase of a latency percentile in percent not taken as a
+    /// regression
+    #[clap(long, default_value = "20")]
+    max_latency_increase: u32,
+}
+
+/// The change from the baseline in percent, unknown for a zero baseline
+fn change_percent(baseline: f64, current: f64) -> Option<f64> {
+    if baseline == 0.0 {
+        return None;
+    }
+
+    Some((current - baseline) / baseline * 100.0)
+}
+
+pub fn list_runs() -> Result<()> {
+    if !Path::new(*DEFAULT_BENCHMARK_RUNS_PATH).exists() {
+        info!("No benchmark run found");
+        return Ok(());
+    }
+
+    let mut ids = read_dir(*DEFAULT_BENCHMARK_RUNS_PATH)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<Result<Vec<_>>>()?;
+    ids.sort();
+
+    for id in ids {
+        match RunReport::load(&id) {
+            Ok(report) => println!(
+                "{} started at {}, {} node(s) of {}:{}, TPS: {:.2}, inclusion p99: {:.1} ms",
+                report.id,
+                report.started_at,
+                report.metadata.nodes,
+                report.metadata.image,
+                report.metadata.tag,
+                report.tps,
+                report.inclusion_latency.p99_ms,
+            ),
+            Err(err) => warn!("Skip run {id}, {err}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the deltas of TPS and latencies between two runs, a regression over
+/// the thresholds fails the command so that it can gate CI
+pub fn compare_runs(args: CompareArgs) -> Result<()> {
+    let CompareArgs {
+        baseline,
+        current,
+        max_tps_drop,
+        max_latency_increase,
+    } = args;
+    let baseline = RunReport::load(&baseline)?;
+    let current = RunReport::load(&current)?;
+
+    if baseline.metadata.config_hash != current.metadata.config_hash
+        || baseline.metadata.nodes != current.metadata.nodes
+    {
+        warn!("The runs are on different chain settings, the deltas may not be comparable");
+    }
+
+    // (metric, baseline, current, whether a higher value is better)
+    let metrics = [
+        ("TPS", baseline.tps, current.tps, true),
+        (
+            "submit latency p50 (ms)",
+            baseline.submit_latency.p50_ms,
+            current.submit_latency.p50_ms,
+            false,
+        ),
+        (
+            "submit latency p99 (ms)",
+            baseline.submit_latency.p99_ms,
+            current.submit_latency.p99_ms,
+            false,
+        ),
+        (
+            "inclusion latency p50 (ms)",
+            baseline.inclusion_latency.p50_ms,
+            current.inclusion_latency.p50_ms,
+            false,
+        ),
+        (
+            "inclusion latency p99 (ms)",
+            baseline.inclusion_latency.p99_ms,
+            current.inclusion_latency.p99_ms,
+            false,
+        ),
+    ];
+
+    println!(
+        "{:<28} {:>12} {:>12} {:>10}",
+        "metric", baseline.id, current.id, "delta"
+    );
+
+    let mut regressions = 0;
+    for (metric, baseline, current, higher_is_better) in metrics {
+        let delta = change_percent(baseline, current);
+        let regressed = delta.map_or(false, |delta| {
+            if higher_is_better {
+                delta < -f64::from(max_tps_drop)
+            } else {
+                delta > f64::from(max_latency_increase)
+            }
+        });
+
+        let delta = delta.map_or_else(|| "n/a".to_string(), |delta| format!("{delta:+.1}%"));
+        let line = format!("{metric:<28} {baseline:>12.2} {current:>12.2} {delta:>10}");
+        if regressed {
+            regressions += 1;
+            println!("{}", line.red());
+        } else {
+            println!("{line}");
+        }
+    }
+
+    if regressions > 0 {
+        return Err(format!(
+            "{regressions} regression(s) over the thresholds, TPS drop: {max_tps_drop}%, latency \
+             increase: {max_latency_increase}%"
+        )
+        .into());
+    }
+
+    info!("No regression found");
+
+    Ok(())
+}
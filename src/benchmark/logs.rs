@@ -0,0 +1,187 @@
+use std::{sync::Mutex, time::Duration};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use clap::Args;
+use colored::*;
+use log::info;
+
+use crate::{
+    axon_nodes::logs::parse_log_line,
+    constants::BENCHMARK_PROGRESS_INTERVAL,
+    docker::DockerApi,
+    types::{DockerArgs, Result},
+};
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct BenchmarkLogsArgs {
+    /// keep streaming until the benchmark exits, its exit code becomes the
+    /// result
+    #[clap(short, long)]
+    follow: bool,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+/// When the first line of the benchmark was logged
+type Started = Mutex<Option<DateTime<FixedOffset>>>;
+
+/// What the benchmark reported of its load so far
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Progress {
+    sent: Option<u64>,
+    tps:  Option<f64>,
+}
+
+impl Progress {
+    /// Take the counts of a line of the benchmark, e.g. `transaction count:
+    /// 7200` or `TPS: 111.36 ms`
+    fn update(&mut self, message: &str) {
+        let (key, value) = match message.split_once(':') {
+            Some((key, value)) => (key.trim().to_lowercase(), value.split_whitespace().next()),
+            None => return,
+        };
+        let value = match value {
+            Some(value) => value,
+            None => return,
+        };
+
+        if key == "tps" {
+            self.tps = value.parse().ok().or(self.tps);
+        } else if (key.contains("transaction") || key.contains("tx"))
+            && (key.contains("count") || key.contains("sent"))
+        {
+            self.sent = value.parse().ok().or(self.sent);
+        }
+    }
+}
+
+/// The seconds from the first line to a line logged at `at`
+fn elapsed(started: &Started, at: DateTime<FixedOffset>) -> Option<f64> {
+    let started = *started.lock().unwrap().get_or_insert(at);
+
+    (at - started)
+        .to_std()
+        .ok()
+        .map(|elapsed| elapsed.as_secs_f64())
+}
+
+/// Print the transactions sent and the TPS every few seconds, as reported in
+/// the output of the benchmark, skipped until it reports any
+async fn show_progress(started: &Started, progress: &Mutex<Progress>) {
+    let interval = Duration::from_secs(BENCHMARK_PROGRESS_INTERVAL);
+    let mut last_sent = None;
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let Progress { sent, tps } = *progress.lock().unwrap();
+        let sent = match sent {
+            Some(sent) => sent,
+            None => continue,
+        };
+        // the TPS of the last interval while the count grows, the one the
+        // benchmark reported otherwise
+        let tps = match last_sent.replace(sent) {
+            Some(last) if sent > last => Some((sent - last) as f64 / interval.as_secs_f64()),
+            _ => tps,
+        };
+
+        let now = DateTime::<FixedOffset>::from(Utc::now());
+        let elapsed = started
+            .lock()
+            .unwrap()
+            .and_then(|started| (now - started).to_std().ok())
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        println!(
+            "{}",
+            format!(
+                "[{elapsed:>7.1}s] {sent} txs sent{}",
+                tps.map_or_else(String::new, |tps| format!(", {tps:.1} TPS"))
+            )
+            .cyan()
+        );
+    }
+}
+
+/// Print the output of the benchmark container with the time since its first
+/// line, and the progress it reports while following it
+///
+/// When following, the exit code of the container becomes the result.
+pub async fn stream_benchmark(docker_api: &DockerApi, follow: bool) -> Result<()> {
+    if docker_api.find_container("benchmark").await?.is_none() {
+        return Err("No benchmark container found".into());
+    }
+
+    let started = Started::default();
+    let progress = Mutex::new(Progress::default());
+    let print_line = |line: String| {
+        let (timestamp, message) = parse_log_line(&line);
+        progress.lock().unwrap().update(message);
+
+        match timestamp.and_then(|timestamp| elapsed(&started, timestamp)) {
+            Some(elapsed) => println!("{} {message}", format!("[{elapsed:>7.1}s]").dimmed()),
+            None => println!("{message}"),
+        }
+    };
+
+    if !follow {
+        docker_api
+            .stream_container_logs("benchmark", None, false, print_line)
+            .await?;
+        return Ok(());
+    }
+
+    tokio::select! {
+        result = docker_api.stream_container_logs("benchmark", None, true, print_line) => result?,
+        _ = show_progress(&started, &progress) => {}
+    }
+
+    let exit_code = docker_api.wait_one_container("benchmark").await?;
+    if exit_code != 0 {
+        return Err(format!("Benchmark exited with code {exit_code}").into());
+    }
+    info!("Benchmark exited successfully");
+
+    Ok(())
+}
+
+pub async fn show_benchmark_logs(args: BenchmarkLogsArgs) -> Result<()> {
+    let BenchmarkLogsArgs {
+        follow,
+        docker_args,
+    } = args;
+
+    let docker_api = DockerApi::new(docker_args).await?;
+
+    stream_benchmark(&docker_api, follow).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_takes_the_counts_of_the_benchmark() {
+        let mut progress = Progress::default();
+
+        for line in [
+            "benchmark case 0: ./benchmark",
+            "benchmark time:  64650.558417998254 ms",
+            "transaction count: 7200",
+            "TPS: 111.3679475658724 ms",
+            "transfer rate: 98.83",
+        ] {
+            progress.update(line);
+        }
+
+        assert_eq!(progress, Progress {
+            sent: Some(7200),
+            tps:  Some(111.3679475658724),
+        });
+
+        // a line without a count keeps the last one
+        progress.update("transaction count: none yet");
+        assert_eq!(progress.sent, Some(7200));
+    }
+}
@@ -0,0 +1,435 @@
+mod accounts;
+mod config;
+mod engine;
+mod history;
+mod logs;
+mod rate;
+pub mod report;
+mod scenario;
+mod series;
+mod verify;
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use chrono::Local;
+use clap::{ArgEnum, ArgMatches, Args, Command, FromArgMatches, Subcommand};
+use log::{info, warn};
+
+use self::{
+    accounts::{exec_accounts, fund_pool, AccountPool, AccountsActions},
+    config::prepare_config,
+    engine::{run_load, LoadArgs},
+    history::{compare_runs, list_runs, CompareArgs},
+    logs::{show_benchmark_logs, stream_benchmark, BenchmarkLogsArgs},
+    rate::RateSchedule,
+    report::{config_hash, show_report, ReportArgs, RunMetadata, RunReport},
+    scenario::load_scenarios,
+    verify::{verify_blocks, VerifyArgs},
+};
+use crate::{
+    axon_nodes::profile::{NodePorts, Profile},
+    constants::{DEFAULT_BENCHMARK_PATH, DEFAULT_NODE_HOST, DEFAULT_PROFILE, ROLE_BENCHMARK},
+    docker::{profile_labels, DockerApi},
+    rpc::RpcClient,
+    sub_command::SubCommand,
+    types::{DockerArgs, Result},
+};
+
+#[derive(Default)]
+pub struct Benchmark {}
+
+#[derive(Subcommand, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+enum BenchmarkActions {
+    /// Start benchmark
+    Start(StartBenchmarkArgs),
+
+    /// Stop benchmark
+    Stop(BenchmarkContainerArgs),
+
+    /// Remove the container of benchmark
+    Rm(RmBenchmarkArgs),
+
+    /// Inspect the container of benchmark
+    Ps(BenchmarkContainerArgs),
+
+    /// Stream the output of the container of benchmark
+    Logs(BenchmarkLogsArgs),
+
+    /// Print the summary of a past run of the native engine, runs in the
+    /// benchmark container have no report
+    Report(ReportArgs),
+
+    /// List the past runs of the native engine, runs in the benchmark
+    /// container are not recorded
+    Runs,
+
+    /// Compare the TPS and latencies of two runs, fails on a regression
+    Compare(CompareArgs),
+
+    /// Measure the throughput committed by a range of blocks, and check a run
+    /// against it
+    Verify(VerifyArgs),
+
+    /// Manage the accounts sending the load
+    #[clap(subcommand)]
+    Accounts(AccountsActions),
+}
+
+/// What sends the load of benchmark
+#[derive(ArgEnum, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+enum BenchmarkEngine {
+    /// the Node.js benchmark in a container
+    Container,
+    /// native transfers sent by axon-cli itself
+    Native,
+}
+
+impl Default for BenchmarkEngine {
+    fn default() -> Self {
+        Self::Container
+    }
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+struct BenchmarkContainerArgs {
+    /// the profile id of the nodes benchmarked
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+struct RmBenchmarkArgs {
+    /// the profile id of the nodes benchmarked
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// force the removal of container
+    #[clap(short, long)]
+    force: bool,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+struct StartBenchmarkArgs {
+    /// the engine sending the load
+    #[clap(short, long, arg_enum, default_value = "container")]
+    engine: BenchmarkEngine,
+
+    /// the working path of benchmark
+    #[clap(short, long, default_value=*DEFAULT_BENCHMARK_PATH)]
+    path: String,
+
+    /// the http endpoints of Axon seperated by ',', defaults to the first
+    /// node of the profile, the container engine takes only one
+    #[clap(short = 'H', long, value_delimiter = ',')]
+    http_endpoint: Vec<String>,
+
+    /// send to all the running nodes of the profile with the native engine
+    #[clap(long, conflicts_with = "http-endpoint")]
+    all_nodes: bool,
+
+    /// the network name of Axon, defaults to the one recorded in the profile
+    #[clap(short = 'N', long)]
+    network: Option<String>,
+
+    /// the profile id of the nodes to benchmark
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+
+    /// don't generate or patch config.json of the container benchmark
+    #[clap(long)]
+    keep_config: bool,
+
+    /// stream the output of the container benchmark until it exits, its exit
+    /// code becomes the result
+    #[clap(short, long)]
+    follow: bool,
+
+    #[clap(flatten)]
+    load_args: LoadArgs,
+
+    #[clap(flatten)]
+    docker_args: DockerArgs,
+}
+
+#[async_trait]
+impl SubCommand for Benchmark {
+    fn get_command(&self) -> Command<'static> {
+        BenchmarkActions::augment_subcommands(Command::new("benchmark")).about("Manage benchmark")
+    }
+
+    async fn exec_command(&mut self, matches: &ArgMatches) -> Result<()> {
+        match BenchmarkActions::from_arg_matches(matches)? {
+            BenchmarkActions::Start(args) => {
+                Benchmark::start_benchmark(args).await?;
+            }
+            BenchmarkActions::Rm(args) => {
+                Benchmark::rm_benchmark(args).await?;
+            }
+            BenchmarkActions::Stop(args) => {
+                Benchmark::stop_benchmark(args).await?;
+            }
+            BenchmarkActions::Ps(args) => {
+                Benchmark::ps_benchmark(args).await?;
+            }
+            BenchmarkActions::Logs(args) => {
+                show_benchmark_logs(args).await?;
+            }
+            BenchmarkActions::Report(args) => {
+                show_report(args)?;
+            }
+            BenchmarkActions::Runs => {
+                list_runs()?;
+            }
+            BenchmarkActions::Compare(args) => {
+                compare_runs(args)?;
+            }
+            BenchmarkActions::Verify(args) => {
+                verify_blocks(args).await?;
+            }
+            BenchmarkActions::Accounts(action) => {
+                exec_accounts(action).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Benchmark {
+    async fn start_benchmark(args: StartBenchmarkArgs) -> Result<()> {
+        let StartBenchmarkArgs {
+            engine,
+            path,
+            http_endpoint,
+            all_nodes,
+            network,
+            profile,
+            keep_config,
+            follow,
+            mut load_args,
+            docker_args,
+        } = args;
+
+        let axon_profile = Profile::load_or_default(&profile)?;
+        let first_node = axon_profile.ports.first();
+        // only the native engine and the funding of a pool need the devnet,
+        // a kept config of the container engine may target another chain
+        if engine == BenchmarkEngine::Native || load_args.pool.mnemonic.is_some() {
+            load_args.pool.resolve_devnet(&axon_profile)?;
+        }
+
+        match engine {
+            BenchmarkEngine::Container => {
+                if all_nodes || http_endpoint.len() > 1 {
+                    return Err("The container engine sends to a single http endpoint".into());
+                }
+
+                // the benchmark joins the network of the nodes, so the port
+                // inside the container is used rather than the published one
+                let http_endpoint = http_endpoint.into_iter().next().unwrap_or_else(|| {
+                    let index = first_node.map_or(1, |ports| ports.index);
+                    format!("http://axon{index}:8000")
+                });
+                // the pool is funded from this machine through the published
+                // port, then the benchmark sends from it
+                if load_args.pool.mnemonic.is_some() {
+                    let rpc = RpcClient::new(default_endpoint(&axon_profile))?;
+                    let mut pool = AccountPool::new(&load_args.pool)?;
+                    fund_pool(
+                        rpc,
+                        &mut pool,
+                        usize::try_from(load_args.batch_size.max(1))?,
+                    )
+                    .await?;
+                }
+                prepare_config(
+                    Path::new(&path),
+                    &axon_profile,
+                    &http_endpoint,
+                    &load_args,
+                    keep_config,
+                )
+                .await?;
+
+                let network = network.as_deref().unwrap_or(&axon_profile.network);
+                let docker_api = DockerApi::new(docker_args).await?;
+                docker_api
+                    .start_benchmark(path, http_endpoint, network, profile)
+                    .await?;
+
+                if follow {
+                    stream_benchmark(&docker_api, true).await?;
+                }
+            }
+            BenchmarkEngine::Native => {
+                if follow {
+                    return Err(
+                        "--follow streams the container benchmark, the native engine \
+                                logs its progress itself"
+                            .into(),
+                    );
+                }
+
+                // the load is sent from this machine through the published ports
+                let http_endpoints = if all_nodes {
+                    Benchmark::discover_nodes(&axon_profile).await?
+                } else if http_endpoint.is_empty() {
+                    vec![default_endpoint(&axon_profile)]
+                } else {
+                    http_endpoint
+                };
+
+                Benchmark::run_native(&axon_profile, http_endpoints, load_args).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The published http endpoints of the nodes of a profile answering RPC
+    async fn discover_nodes(axon_profile: &Profile) -> Result<Vec<String>> {
+        let mut endpoints = Vec::new();
+
+        for ports in axon_profile.ports.iter() {
+            let endpoint = published_endpoint(axon_profile, ports);
+            let rpc = RpcClient::new(&endpoint)?;
+
+            match rpc.chain_id().await {
+                Ok(_) => endpoints.push(endpoint),
+                Err(err) => warn!("Skip node {} at {endpoint}, {err}", ports.index),
+            }
+        }
+
+        if endpoints.is_empty() {
+            return Err(format!("No running node found in profile {}", axon_profile.id).into());
+        }
+        info!("Found {} running node(s)", endpoints.len());
+
+        Ok(endpoints)
+    }
+
+    /// Run the native engine, then print and save the report of the run
+    async fn run_native(
+        axon_profile: &Profile,
+        http_endpoints: Vec<String>,
+        load_args: LoadArgs,
+    ) -> Result<()> {
+        let scenarios = load_scenarios(
+            load_args.scenario,
+            load_args.scenario_file.as_deref(),
+            axon_profile,
+        )?;
+        let scenario = load_args.scenario_file.clone().unwrap_or_else(|| {
+            load_args
+                .scenario
+                .to_possible_value()
+                .map_or_else(String::new, |value| value.get_name().to_string())
+        });
+
+        let id = RunReport::new_id()?;
+        let started_at = Local::now().to_rfc3339();
+        let metadata = RunMetadata {
+            engine: "native".to_string(),
+            scenario,
+            profile: axon_profile.id.clone(),
+            http_endpoint: http_endpoints.join(","),
+            image: axon_profile.image.clone(),
+            tag: axon_profile.tag.clone(),
+            nodes: axon_profile.ports.len(),
+            config_hash: config_hash(axon_profile),
+            accounts: load_args.pool.accounts,
+            rate: RateSchedule::new(&load_args)?.map(|rate| rate.to_string()),
+            concurrency: load_args.concurrency,
+            batch_size: load_args.batch_size,
+            duration: load_args.duration,
+        };
+
+        let result = run_load(&http_endpoints, &load_args, &scenarios, &id).await?;
+        let report = RunReport::new(id, started_at, metadata, result);
+        report.print();
+        info!(
+            "Report of run {} saved to {}",
+            report.id,
+            report.save()?.display()
+        );
+
+        Ok(())
+    }
+
+    /// The names of the benchmark containers of a profile, found by their
+    /// labels
+    async fn find_benchmarks(docker_api: &DockerApi, profile: &str) -> Result<Vec<String>> {
+        let containers = docker_api
+            .list_containers(&profile_labels(profile, ROLE_BENCHMARK))
+            .await?;
+
+        Ok(containers
+            .into_iter()
+            .filter_map(|container| container.names?.into_iter().next())
+            .map(|name| name.trim_start_matches('/').to_string())
+            .collect())
+    }
+
+    async fn rm_benchmark(args: RmBenchmarkArgs) -> Result<()> {
+        let RmBenchmarkArgs {
+            profile,
+            force,
+            docker_args,
+        } = args;
+
+        let docker_api = DockerApi::new(docker_args).await?;
+        let names = Benchmark::find_benchmarks(&docker_api, &profile).await?;
+
+        Ok(docker_api.remove_containers(names, force).await?)
+    }
+
+    async fn stop_benchmark(args: BenchmarkContainerArgs) -> Result<()> {
+        let BenchmarkContainerArgs {
+            profile,
+            docker_args,
+        } = args;
+
+        let docker_api = DockerApi::new(docker_args).await?;
+        let names = Benchmark::find_benchmarks(&docker_api, &profile).await?;
+
+        Ok(docker_api.stop_containers(names).await?)
+    }
+
+    async fn ps_benchmark(args: BenchmarkContainerArgs) -> Result<()> {
+        let BenchmarkContainerArgs {
+            profile,
+            docker_args,
+        } = args;
+
+        let docker_api = DockerApi::new(docker_args).await?;
+        let names = Benchmark::find_benchmarks(&docker_api, &profile).await?;
+        if names.is_empty() {
+            info!("No benchmark container of profile {profile}");
+        }
+
+        Ok(docker_api.inspect_containers(names).await?)
+    }
+}
+
+/// The published http endpoint of the first node of a profile
+fn default_endpoint(axon_profile: &Profile) -> String {
+    axon_profile.ports.first().map_or_else(
+        || format!("http://{DEFAULT_NODE_HOST}:8000"),
+        |ports| published_endpoint(axon_profile, ports),
+    )
+}
+
+fn published_endpoint(axon_profile: &Profile, ports: &NodePorts) -> String {
+    format!(
+        "http://{}:{}",
+        axon_profile.node_address(ports.index),
+        ports.http
+    )
+}
@@ -0,0 +1,215 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use clap::ArgEnum;
+
+use super::engine::LoadArgs;
+use crate::types::Result;
+
+/// How the target rate of the open-loop load changes over a run
+#[derive(ArgEnum, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum RateProfile {
+    /// `--rate` all along
+    Constant,
+    /// from `--rate` to `--rate-to` evenly
+    Linear,
+    /// from `--rate` to `--rate-to` in `--steps` equal steps
+    Step,
+}
+
+impl Default for RateProfile {
+    fn default() -> Self {
+        Self::Constant
+    }
+}
+
+/// The target rate of an open-loop run, from which the workers claim when to
+/// send their batches regardless of how the node responds
+pub struct RateSchedule {
+    profile:  RateProfile,
+    from:     f64,
+    to:       f64,
+    steps:    u32,
+    /// the length of the run in seconds
+    duration: f64,
+    /// the transactions claimed by the workers so far
+    claimed:  AtomicUsize,
+}
+
+impl RateSchedule {
+    /// The schedule of the rate arguments, none for a closed-loop run
+    pub fn new(args: &LoadArgs) -> Result<Option<Self>> {
+        let from = match args.rate {
+            Some(rate) => rate,
+            None => return Ok(None),
+        };
+        let to = match (args.rate_profile, args.rate_to) {
+            (RateProfile::Constant, _) => from,
+            (_, Some(to)) => to,
+            (_, None) => return Err("A linear or step rate needs --rate-to".into()),
+        };
+
+        if from == 0 && to == 0 {
+            return Err("The target rate must be positive".into());
+        }
+        if args.duration == 0 {
+            return Err("A rate needs a positive duration".into());
+        }
+        if args.steps == 0 {
+            return Err("A step rate needs at least one step".into());
+        }
+
+        Ok(Some(Self {
+            profile:  args.rate_profile,
+            from:     f64::from(from),
+            to:       f64::from(to),
+            steps:    args.steps,
+            duration: args.duration as f64,
+            claimed:  AtomicUsize::new(0),
+        }))
+    }
+
+    fn step_rate(&self, step: u32) -> f64 {
+        self.from + (self.to - self.from) * f64::from(step) / f64::from(self.steps - 1).max(1.0)
+    }
+
+    /// The target TPS `t` seconds into the run
+    pub fn target(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, self.duration);
+
+        match self.profile {
+            RateProfile::Constant => self.from,
+            RateProfile::Linear => self.from + (self.to - self.from) * t / self.duration,
+            RateProfile::Step => {
+                let step = (t / self.duration * f64::from(self.steps)) as u32;
+                self.step_rate(step.min(self.steps - 1))
+            }
+        }
+    }
+
+    /// The transactions to offer in the first `t` seconds of the run
+    fn offered(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, self.duration);
+
+        match self.profile {
+            RateProfile::Constant => self.from * t,
+            RateProfile::Linear => {
+                self.from * t + (self.to - self.from) * t * t / (2.0 * self.duration)
+            }
+            RateProfile::Step => {
+                let width = self.duration / f64::from(self.steps);
+                (0..self.steps)
+                    .map(|step| {
+                        let span = (t - f64::from(step) * width).clamp(0.0, width);
+                        self.step_rate(step) * span
+                    })
+                    .sum()
+            }
+        }
+    }
+
+    /// Claim the next `count` transactions, returns when they are due from the
+    /// start of the run, or none past its end
+    pub fn claim(&self, count: usize) -> Option<Duration> {
+        let last = (self.claimed.fetch_add(count, Ordering::SeqCst) + count) as f64;
+        if last > self.offered(self.duration) {
+            return None;
+        }
+
+        // the offered transactions only grow over time, so bisect for the
+        // moment the last of the claimed ones is due
+        let (mut low, mut high) = (0.0, self.duration);
+        for _ in 0..64 {
+            let mid = (low + high) / 2.0;
+            if self.offered(mid) < last {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Some(Duration::from_secs_f64(high))
+    }
+}
+
+impl fmt::Display for RateSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.profile {
+            RateProfile::Constant => write!(f, "constant {} TPS", self.from),
+            RateProfile::Linear => write!(f, "linear from {} to {} TPS", self.from, self.to),
+            RateProfile::Step => write!(
+                f,
+                "{} steps from {} to {} TPS",
+                self.steps, self.from, self.to
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(
+        profile: RateProfile,
+        from: u32,
+        to: u32,
+        steps: u32,
+        duration: u64,
+    ) -> RateSchedule {
+        let args = LoadArgs {
+            rate: Some(from),
+            rate_profile: profile,
+            rate_to: Some(to),
+            steps,
+            duration,
+            ..Default::default()
+        };
+
+        RateSchedule::new(&args).unwrap().unwrap()
+    }
+
+    fn assert_close(left: f64, right: f64) {
+        assert!((left - right).abs() < 1e-6, "{left} != {right}");
+    }
+
+    #[test]
+    fn offered_integrates_the_target_rate() {
+        let constant = schedule(RateProfile::Constant, 10, 0, 1, 10);
+        assert_close(constant.offered(5.0), 50.0);
+        assert_close(constant.offered(20.0), 100.0);
+
+        let linear = schedule(RateProfile::Linear, 0, 20, 1, 10);
+        assert_close(linear.offered(5.0), 25.0);
+        assert_close(linear.offered(10.0), 100.0);
+
+        let step = schedule(RateProfile::Step, 10, 30, 3, 30);
+        assert_close(step.offered(15.0), 200.0);
+        assert_close(step.offered(30.0), 600.0);
+    }
+
+    #[test]
+    fn claim_spreads_batches_until_the_end() {
+        let rate = schedule(RateProfile::Constant, 10, 0, 1, 2);
+
+        assert_close(rate.claim(5).unwrap().as_secs_f64(), 0.5);
+        assert_close(rate.claim(5).unwrap().as_secs_f64(), 1.0);
+        assert_close(rate.claim(10).unwrap().as_secs_f64(), 2.0);
+        assert_eq!(rate.claim(1), None);
+    }
+
+    #[test]
+    fn new_needs_a_rate_to_for_other_profiles() {
+        let args = LoadArgs {
+            rate: Some(10),
+            rate_profile: RateProfile::Linear,
+            ..Default::default()
+        };
+
+        assert!(RateSchedule::new(&args).is_err());
+        assert!(RateSchedule::new(&LoadArgs::default()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,509 @@
+use std::{
+    fs::{create_dir, create_dir_all, read, read_dir, write},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use axon_protocol::types::{Hasher, H256};
+use chrono::Local;
+use clap::{ArgEnum, Args};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    engine::{EndpointResult, LoadResult},
+    series::SeriesPoint,
+};
+use crate::{
+    axon_nodes::profile::Profile,
+    constants::DEFAULT_BENCHMARK_RUNS_PATH,
+    types::Result,
+    utils::{from_json_file, to_json_file},
+};
+
+#[derive(ArgEnum, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl Default for ReportFormat {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct ReportArgs {
+    /// the id of the run
+    run_id: String,
+
+    /// the format of the summary
+    #[clap(short, long, arg_enum, default_value = "text")]
+    format: ReportFormat,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub struct LatencyStats {
+    pub avg_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    pub fn new(latencies: &[Duration]) -> Self {
+        let mut sorted = latencies
+            .iter()
+            .map(|latency| latency.as_secs_f64() * 1000.0)
+            .collect::<Vec<_>>();
+        sorted.sort_by(f64::total_cmp);
+
+        let percentile = |percent: usize| {
+            sorted
+                .get(sorted.len().saturating_sub(1) * percent / 100)
+                .copied()
+                .unwrap_or_default()
+        };
+
+        Self {
+            avg_ms: sorted.iter().sum::<f64>() / sorted.len().max(1) as f64,
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+            max_ms: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+impl std::fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "avg {:.1} ms, p50 {:.1} ms, p90 {:.1} ms, p99 {:.1} ms, max {:.1} ms",
+            self.avg_ms, self.p50_ms, self.p90_ms, self.p99_ms, self.max_ms
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct BlockStats {
+    pub number:    u64,
+    pub timestamp: u64,
+    pub tx_count:  usize,
+    pub gas_used:  u64,
+}
+
+/// The transactions sent to an endpoint, where a low acceptance rate or a
+/// slow inclusion against the other endpoints points at the mempool gossip
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct EndpointStats {
+    pub endpoint:          String,
+    pub sent:              usize,
+    pub accepted:          usize,
+    pub rejected:          usize,
+    pub failed:            usize,
+    pub included:          usize,
+    /// the accepted transactions in percent of the sent ones
+    pub acceptance_rate:   f64,
+    pub inclusion_latency: LatencyStats,
+}
+
+impl From<EndpointResult> for EndpointStats {
+    fn from(result: EndpointResult) -> Self {
+        Self {
+            acceptance_rate:   result.accepted as f64 * 100.0 / result.sent.max(1) as f64,
+            included:          result.inclusion_latencies.len(),
+            inclusion_latency: LatencyStats::new(&result.inclusion_latencies),
+            endpoint:          result.endpoint,
+            sent:              result.sent,
+            accepted:          result.accepted,
+            rejected:          result.rejected,
+            failed:            result.failed,
+        }
+    }
+}
+
+/// What a benchmark ran against and with
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct RunMetadata {
+    pub engine:        String,
+    /// the built-in scenario or the scenario file
+    pub scenario:      String,
+    pub profile:       String,
+    pub http_endpoint: String,
+    pub image:         String,
+    pub tag:           String,
+    pub nodes:         usize,
+    /// the hash of the genesis and config files of the nodes
+    pub config_hash:   Option<H256>,
+    pub accounts:      u32,
+    /// the target rate of an open-loop run
+    pub rate:          Option<String>,
+    pub concurrency:   u32,
+    pub batch_size:    u32,
+    pub duration:      u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct RunReport {
+    pub id:                String,
+    pub started_at:        String,
+    pub metadata:          RunMetadata,
+    pub elapsed_secs:      f64,
+    pub sent:              usize,
+    pub accepted:          usize,
+    pub rejected:          usize,
+    pub failed:            usize,
+    pub included:          usize,
+    /// the included transactions per second of sending
+    pub tps:               f64,
+    pub submit_latency:    LatencyStats,
+    pub inclusion_latency: LatencyStats,
+    pub last_error:        Option<String>,
+    pub blocks:            Vec<BlockStats>,
+    #[serde(default)]
+    pub endpoints:         Vec<EndpointStats>,
+    #[serde(default)]
+    pub series:            Vec<SeriesPoint>,
+    /// the transactions accepted by the endpoints, to tell the ones the chain
+    /// dropped
+    #[serde(default)]
+    pub accepted_hashes:   Vec<H256>,
+}
+
+/// Hash the genesis and config files of the nodes of a profile, so that runs
+/// on different chain settings can be told apart
+pub fn config_hash(profile: &Profile) -> Option<H256> {
+    let mut files = read_dir(Path::new(&profile.path).join("nodes"))
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| {
+                    name == "genesis.json"
+                        || (name.starts_with("config_") && name.ends_with(".toml"))
+                })
+        })
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        return None;
+    }
+    files.sort();
+
+    let mut content = Vec::new();
+    for file in files {
+        content.extend(read(file).ok()?);
+    }
+
+    Some(Hasher::digest(content))
+}
+
+/// The transactions after the first block per second of the block timestamps,
+/// up to the last block with transactions as the blocks after the load are
+/// empty, `None` if the blocks span no time
+pub fn block_tps(blocks: &[BlockStats]) -> Option<f64> {
+    let last = blocks.iter().rposition(|block| block.tx_count > 0)?;
+    let blocks = &blocks[..=last];
+    let span = blocks[last].timestamp.saturating_sub(blocks[0].timestamp);
+    if span == 0 {
+        return None;
+    }
+
+    let txs = blocks[1..]
+        .iter()
+        .map(|block| block.tx_count)
+        .sum::<usize>();
+
+    Some(txs as f64 / span as f64)
+}
+
+impl RunReport {
+    /// A new id of a run from the current time, the path of the run is created
+    /// to claim the id against runs started in the same second
+    pub fn new_id() -> Result<String> {
+        let time = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        create_dir_all(*DEFAULT_BENCHMARK_RUNS_PATH)?;
+
+        let mut id = time.clone();
+        let mut suffix = 1;
+        loop {
+            match create_dir(Self::path(&id)) {
+                Ok(()) => return Ok(id),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    suffix += 1;
+                    id = format!("{time}-{suffix}");
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    pub fn new(id: String, started_at: String, metadata: RunMetadata, result: LoadResult) -> Self {
+        let included = result.inclusion_latencies.len();
+        let elapsed_secs = result.elapsed.as_secs_f64();
+        // the load stops at the duration, but its transactions are packed in
+        // the blocks after it
+        let tps = block_tps(&result.blocks)
+            .unwrap_or_else(|| included as f64 / elapsed_secs.max(f64::EPSILON));
+
+        Self {
+            id,
+            started_at,
+            metadata,
+            elapsed_secs,
+            sent: result.sent,
+            accepted: result.submit_latencies.len(),
+            rejected: result.rejected,
+            failed: result.failed,
+            included,
+            tps,
+            submit_latency: LatencyStats::new(&result.submit_latencies),
+            inclusion_latency: LatencyStats::new(&result.inclusion_latencies),
+            last_error: result.last_error,
+            blocks: result.blocks,
+            endpoints: result.endpoints.into_iter().map(Into::into).collect(),
+            series: result.series,
+            accepted_hashes: result.accepted_hashes,
+        }
+    }
+
+    pub fn path(id: &str) -> PathBuf {
+        Path::new(*DEFAULT_BENCHMARK_RUNS_PATH).join(id)
+    }
+
+    pub fn load(id: &str) -> Result<Self> {
+        let path = Self::path(id).join("report.json");
+
+        if !path.exists() {
+            return Err(format!("Benchmark run {id} not found").into());
+        }
+
+        from_json_file(path)
+    }
+
+    /// Save the report as `report.json`, the blocks as `blocks.csv` and the
+    /// time series as `series.csv` under the path of the run
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::path(&self.id);
+        create_dir_all(&path)?;
+
+        to_json_file(self, path.join("report.json"))?;
+
+        let mut blocks = "number,timestamp,tx_count,gas_used\n".to_string();
+        for block in self.blocks.iter() {
+            blocks += &format!(
+                "{},{},{},{}\n",
+                block.number, block.timestamp, block.tx_count, block.gas_used
+            );
+        }
+        write(path.join("blocks.csv"), blocks)?;
+
+        let mut series = "second,target_tps,offered_tps,accepted_tps,rejected_tps,failed_tps,\
+                          achieved_tps,submit_p50_ms,submit_p99_ms,inclusion_p50_ms,\
+                          inclusion_p99_ms\n"
+            .to_string();
+        for point in self.series.iter() {
+            series += &format!(
+                "{},{},{},{},{},{},{},{:.1},{:.1},{:.1},{:.1}\n",
+                point.second,
+                point
+                    .target_tps
+                    .map_or_else(String::new, |target| format!("{target:.0}")),
+                point.offered_tps,
+                point.accepted_tps,
+                point.rejected_tps,
+                point.failed_tps,
+                point.achieved_tps,
+                point.submit_latency.p50_ms,
+                point.submit_latency.p99_ms,
+                point.inclusion_latency.p50_ms,
+                point.inclusion_latency.p99_ms,
+            );
+        }
+        write(path.join("series.csv"), series)?;
+
+        Ok(path)
+    }
+
+    fn csv(&self) -> String {
+        let RunMetadata {
+            engine,
+            tag,
+            nodes,
+            config_hash,
+            ..
+        } = &self.metadata;
+
+        format!(
+            "id,started_at,engine,tag,nodes,config_hash,elapsed_secs,sent,accepted,rejected,failed,included,tps,submit_p50_ms,submit_p99_ms,inclusion_p50_ms,inclusion_p99_ms\n\
+             {},{},{},{},{},{},{:.3},{},{},{},{},{},{:.2},{:.1},{:.1},{:.1},{:.1}",
+            self.id,
+            self.started_at,
+            engine,
+            tag,
+            nodes,
+            config_hash.map_or_else(String::new, |hash| format!("{hash:?}")),
+            self.elapsed_secs,
+            self.sent,
+            self.accepted,
+            self.rejected,
+            self.failed,
+            self.included,
+            self.tps,
+            self.submit_latency.p50_ms,
+            self.submit_latency.p99_ms,
+            self.inclusion_latency.p50_ms,
+            self.inclusion_latency.p99_ms,
+        )
+    }
+
+    pub fn print(&self) {
+        let RunMetadata {
+            engine,
+            scenario,
+            profile,
+            http_endpoint,
+            image,
+            tag,
+            nodes,
+            config_hash,
+            rate,
+            ..
+        } = &self.metadata;
+        let gas_used = self.blocks.iter().map(|block| block.gas_used).sum::<u64>();
+        let busiest = self.blocks.iter().map(|block| block.tx_count).max();
+
+        println!("/////////////////////////////////////////////////////");
+        println!("run: {} started at {}", self.id, self.started_at);
+        println!(
+            "target: {nodes} node(s) of profile {profile}, {image}:{tag}, {engine} engine to \
+             {http_endpoint}"
+        );
+        println!("scenario: {scenario}");
+        println!("rate: {}", rate.as_deref().unwrap_or("full speed"));
+        println!(
+            "config hash: {}",
+            config_hash.map_or_else(|| "unknown".to_string(), |hash| format!("{hash:?}"))
+        );
+        println!("benchmark time: {:.2} s", self.elapsed_secs);
+        println!(
+            "transactions: {} sent, {} accepted, {} rejected, {} failed, {} included",
+            self.sent, self.accepted, self.rejected, self.failed, self.included
+        );
+        println!("TPS: {:.2}", self.tps);
+        if let Some(peak) = self.series.iter().max_by_key(|point| point.achieved_tps) {
+            println!(
+                "peak: {} TPS achieved in second {} with {} offered",
+                peak.achieved_tps,
+                peak.second + 1,
+                peak.offered_tps
+            );
+        }
+        println!("submit latency: {}", self.submit_latency);
+        println!("inclusion latency: {}", self.inclusion_latency);
+        println!(
+            "blocks: {}, most txs in a block: {}, gas used: {gas_used}",
+            self.blocks.len(),
+            busiest.unwrap_or_default()
+        );
+        if self.endpoints.len() > 1 {
+            for stats in self.endpoints.iter() {
+                println!(
+                    "endpoint {}: {} sent, {} accepted ({:.1}%), {} rejected, {} failed, {} \
+                     included, inclusion p50 {:.1} ms, p99 {:.1} ms",
+                    stats.endpoint,
+                    stats.sent,
+                    stats.accepted,
+                    stats.acceptance_rate,
+                    stats.rejected,
+                    stats.failed,
+                    stats.included,
+                    stats.inclusion_latency.p50_ms,
+                    stats.inclusion_latency.p99_ms
+                );
+            }
+        }
+        if let Some(err) = &self.last_error {
+            println!("last error: {err}");
+        }
+        println!("/////////////////////////////////////////////////////");
+    }
+}
+
+pub fn show_report(args: ReportArgs) -> Result<()> {
+    let ReportArgs { run_id, format } = args;
+    let report = RunReport::load(&run_id)?;
+
+    match format {
+        ReportFormat::Text => report.print(),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        ReportFormat::Csv => println!("{}", report.csv()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: u64, timestamp: u64, tx_count: usize) -> BlockStats {
+        BlockStats {
+            number,
+            timestamp,
+            tx_count,
+            gas_used: 0,
+        }
+    }
+
+    #[test]
+    fn block_tps_ends_at_the_last_block_with_transactions() {
+        let blocks = [
+            block(1, 100, 50),
+            block(2, 101, 200),
+            block(3, 103, 100),
+            block(4, 104, 0),
+            block(5, 110, 0),
+        ];
+
+        assert_eq!(block_tps(&blocks), Some(100.0));
+        assert_eq!(block_tps(&blocks[..1]), None);
+        assert_eq!(block_tps(&blocks[3..]), None);
+    }
+
+    #[test]
+    fn latency_stats_of_no_latency() {
+        let stats = LatencyStats::new(&[]);
+
+        assert_eq!(stats.avg_ms, 0.0);
+        assert_eq!(stats.p99_ms, 0.0);
+        assert_eq!(stats.max_ms, 0.0);
+    }
+
+    #[test]
+    fn latency_stats_take_percentiles_of_sorted_latencies() {
+        let latencies = (1..=100)
+            .rev()
+            .map(Duration::from_millis)
+            .collect::<Vec<_>>();
+
+        let stats = LatencyStats::new(&latencies);
+
+        let expected = [50.5, 50.0, 90.0, 99.0, 100.0];
+        let actual = [
+            stats.avg_ms,
+            stats.p50_ms,
+            stats.p90_ms,
+            stats.p99_ms,
+            stats.max_ms,
+        ];
+        for (expected, actual) in expected.iter().zip(actual) {
+            assert!((expected - actual).abs() < 1e-6, "{expected} != {actual}");
+        }
+    }
+}
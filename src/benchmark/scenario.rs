@@ -0,0 +1,452 @@
+use std::{fs::read_to_string, path::Path};
+
+use axon_protocol::types::{Hasher, TransactionAction, H160, H256, U256};
+use clap::ArgEnum;
+use ethers_core::abi::Token;
+use serde::{Deserialize, Serialize};
+
+use super::engine::{Chain, Sender};
+use crate::{
+    axon_nodes::{config::contract_address, profile::Profile},
+    constants::{CROSS_CHAIN_ABI, TOKEN_ABI, TOKEN_CONTRACT, TRANSFER_GAS_LIMIT},
+    types::Result,
+    utils::{find_toml_value, hex_decode},
+};
+
+/// A contract writing the number of fresh storage slots given in its calldata,
+/// assembled by hand since no such artifact is bundled
+const STORAGE_CONTRACT_CODE: &str = "602180600b6000396000f3600035600054809101905b8181101560\
+                                     1c5760010160018155600a565b60005500";
+const TOKEN_GAS_LIMIT: u64 = 100_000;
+const DEPLOY_GAS_LIMIT: u64 = 5_000_000;
+const STORAGE_GAS_LIMIT: u64 = 50_000;
+const STORAGE_SLOT_GAS: u64 = 25_000;
+const CROSS_CHAIN_GAS_LIMIT: u64 = 1_000_000;
+/// the tokens minted to each sender for ERC20 transfers
+const TOKEN_MINTED: u64 = 1_000_000_000_000;
+
+/// A built-in scenario, used alone with its default parameters
+#[derive(ArgEnum, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum ScenarioKind {
+    /// native transfers
+    Transfer,
+    /// transfers of a `MirrorToken` deployed before the load
+    Erc20Transfer,
+    /// deploys of `MirrorToken`
+    Deploy,
+    /// calls writing fresh storage slots
+    Storage,
+    /// `lockAT` calls of the `CrossChain` contract
+    CrossChain,
+}
+
+impl Default for ScenarioKind {
+    fn default() -> Self {
+        Self::Transfer
+    }
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn default_value() -> u64 {
+    1
+}
+
+fn default_slots() -> u64 {
+    10
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Scenario {
+    Transfer {
+        #[serde(default = "default_value")]
+        value: u64,
+    },
+    Erc20Transfer {
+        #[serde(default = "default_value")]
+        amount: u64,
+    },
+    Deploy,
+    Storage {
+        /// the fresh slots written by a call
+        #[serde(default = "default_slots")]
+        slots: u64,
+    },
+    CrossChain {
+        #[serde(default = "default_value")]
+        value:   u64,
+        /// the CKB address to lock to
+        #[serde(default)]
+        to:      String,
+        /// defaults to the `CrossChain` contract in the config of the nodes
+        #[serde(default)]
+        address: Option<H160>,
+    },
+}
+
+/// An item of a scenario file, a JSON array mixing scenarios by weight, e.g.
+/// `[{"kind": "transfer", "weight": 3}, {"kind": "storage", "slots": 20}]`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WeightedScenario {
+    #[serde(default = "default_weight")]
+    pub weight:   u32,
+    #[serde(flatten)]
+    pub scenario: Scenario,
+}
+
+impl From<ScenarioKind> for Scenario {
+    fn from(kind: ScenarioKind) -> Self {
+        match kind {
+            ScenarioKind::Transfer => Scenario::Transfer {
+                value: default_value(),
+            },
+            ScenarioKind::Erc20Transfer => Scenario::Erc20Transfer {
+                amount: default_value(),
+            },
+            ScenarioKind::Deploy => Scenario::Deploy,
+            ScenarioKind::Storage => Scenario::Storage {
+                slots: default_slots(),
+            },
+            ScenarioKind::CrossChain => Scenario::CrossChain {
+                value:   default_value(),
+                to:      "".to_string(),
+                address: None,
+            },
+        }
+    }
+}
+
+/// Read the scenarios from a file, or take a single built-in one, and fill in
+/// the `CrossChain` contract of the profile
+pub fn load_scenarios(
+    kind: ScenarioKind,
+    file: Option<&str>,
+    profile: &Profile,
+) -> Result<Vec<WeightedScenario>> {
+    let mut scenarios = match file {
+        Some(file) => serde_json::from_str::<Vec<WeightedScenario>>(&read_to_string(file)?)?,
+        None => vec![WeightedScenario {
+            weight:   1,
+            scenario: kind.into(),
+        }],
+    };
+
+    if scenarios.iter().all(|scenario| scenario.weight == 0) {
+        return Err("No scenario with a positive weight".into());
+    }
+
+    for scenario in scenarios.iter_mut() {
+        if let Scenario::CrossChain { address, .. } = &mut scenario.scenario {
+            if address.is_none() {
+                let config =
+                    read_to_string(Path::new(&profile.path).join("nodes").join("config_1.toml"))?;
+                let contract = find_toml_value(&config, "crosschain_contract_address")
+                    .ok_or("No CrossChain contract found in the config of the nodes")?;
+
+                *address = Some(H160::from_slice(&hex_decode(contract)?));
+            }
+        }
+    }
+
+    Ok(scenarios)
+}
+
+/// A transaction sent by every sender, encoded once
+#[derive(Debug, Clone)]
+struct Call {
+    action:    TransactionAction,
+    value:     U256,
+    data:      Vec<u8>,
+    gas_limit: u64,
+}
+
+/// The calls of the scenarios and the order to send them in, where a call
+/// appears as many times as its weight
+pub struct Mix {
+    calls:    Vec<Call>,
+    schedule: Vec<usize>,
+}
+
+impl Mix {
+    /// Sign the `n`th transaction of the mix with the next nonce of a sender
+    pub fn sign(&self, n: usize, sender: &mut Sender, chain: &Chain) -> (String, H256) {
+        let Call {
+            action,
+            value,
+            data,
+            gas_limit,
+        } = &self.calls[self.schedule[n % self.schedule.len()]];
+
+        sender.sign(action.clone(), *value, data.clone(), *gas_limit, chain)
+    }
+}
+
+/// Build the transactions of the funder setting up the scenarios, and the
+/// mix of calls of the senders
+///
+/// The funder deploys the token and the storage contract first, and mints
+/// tokens to the senders if ERC20 transfers are mixed in.
+pub fn prepare_mix(
+    scenarios: &[WeightedScenario],
+    funder: &mut Sender,
+    senders: &[Sender],
+    chain: &Chain,
+) -> Result<(Vec<String>, Mix)> {
+    let mut setup = Vec::new();
+    let mut calls = Vec::new();
+    let mut schedule = Vec::new();
+    let mut deployed_token = None;
+    let mut storage_contract = None;
+
+    let deploy_token_data =
+        TOKEN_ABI
+            .constructor()
+            .unwrap()
+            .encode_input(hex_decode(TOKEN_CONTRACT.bytecode)?, &[
+                Token::String("Benchmark".to_string()),
+                Token::String("BM".to_string()),
+                Token::Uint(8.into()),
+            ])?;
+
+    for (index, WeightedScenario { weight, scenario }) in scenarios.iter().enumerate() {
+        let call = match scenario {
+            Scenario::Transfer { value } => Call {
+                action:    TransactionAction::Call(funder.address),
+                value:     (*value).into(),
+                data:      Vec::new(),
+                gas_limit: TRANSFER_GAS_LIMIT,
+            },
+            Scenario::Erc20Transfer { amount } => {
+                let token = match deployed_token {
+                    Some(token) => token,
+                    None => {
+                        let address = deploy_token(
+                            &mut setup,
+                            funder,
+                            senders,
+                            deploy_token_data.clone(),
+                            chain,
+                        )?;
+                        *deployed_token.insert(address)
+                    }
+                };
+
+                Call {
+                    action:    TransactionAction::Call(token),
+                    value:     0.into(),
+                    data:      TOKEN_ABI.function("transfer")?.encode_input(&[
+                        Token::Address(funder.address),
+                        Token::Uint((*amount).into()),
+                    ])?,
+                    gas_limit: TOKEN_GAS_LIMIT,
+                }
+            }
+            Scenario::Deploy => Call {
+                action:    TransactionAction::Create,
+                value:     0.into(),
+                data:      deploy_token_data.clone(),
+                gas_limit: DEPLOY_GAS_LIMIT,
+            },
+            Scenario::Storage { slots } => {
+                let contract = match storage_contract {
+                    Some(contract) => contract,
+                    None => {
+                        let address =
+                            contract_address(&funder.address, u32::try_from(funder.nonce)?);
+                        setup.push(
+                            funder
+                                .sign(
+                                    TransactionAction::Create,
+                                    0.into(),
+                                    hex_decode(STORAGE_CONTRACT_CODE)?,
+                                    DEPLOY_GAS_LIMIT,
+                                    chain,
+                                )
+                                .0,
+                        );
+                        *storage_contract.insert(address)
+                    }
+                };
+
+                Call {
+                    action:    TransactionAction::Call(contract),
+                    value:     0.into(),
+                    data:      ethers_core::abi::encode(&[Token::Uint((*slots).into())]),
+                    gas_limit: STORAGE_GAS_LIMIT + STORAGE_SLOT_GAS * slots,
+                }
+            }
+            Scenario::CrossChain { value, to, address } => Call {
+                action:    TransactionAction::Call(address.ok_or("No CrossChain contract given")?),
+                value:     (*value).into(),
+                data:      CROSS_CHAIN_ABI
+                    .function("lockAT")?
+                    .encode_input(&[Token::String(to.clone())])?,
+                gas_limit: CROSS_CHAIN_GAS_LIMIT,
+            },
+        };
+
+        calls.push(call);
+        schedule.extend((0..*weight).map(|_| index));
+    }
+
+    Ok((setup, Mix { calls, schedule }))
+}
+
+/// Deploy a token, then grant the funder the role to mint it and mint to the
+/// senders
+fn deploy_token(
+    setup: &mut Vec<String>,
+    funder: &mut Sender,
+    senders: &[Sender],
+    data: Vec<u8>,
+    chain: &Chain,
+) -> Result<H160> {
+    let token = contract_address(&funder.address, u32::try_from(funder.nonce)?);
+    setup.push(
+        funder
+            .sign(
+                TransactionAction::Create,
+                0.into(),
+                data,
+                DEPLOY_GAS_LIMIT,
+                chain,
+            )
+            .0,
+    );
+
+    let grant_role = TOKEN_ABI.function("grantRole")?.encode_input(&[
+        Token::FixedBytes(Hasher::digest("MANAGER_ROLE").as_bytes().to_vec()),
+        Token::Address(funder.address),
+    ])?;
+    setup.push(
+        funder
+            .sign(
+                TransactionAction::Call(token),
+                0.into(),
+                grant_role,
+                TOKEN_GAS_LIMIT,
+                chain,
+            )
+            .0,
+    );
+
+    for sender in senders {
+        let mint = TOKEN_ABI.function("mint")?.encode_input(&[
+            Token::Address(sender.address),
+            Token::Uint(TOKEN_MINTED.into()),
+        ])?;
+        setup.push(
+            funder
+                .sign(
+                    TransactionAction::Call(token),
+                    0.into(),
+                    mint,
+                    TOKEN_GAS_LIMIT,
+                    chain,
+                )
+                .0,
+        );
+    }
+
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepare(scenarios: &[WeightedScenario], senders: usize) -> (Sender, Vec<String>, Mix) {
+        let chain = Chain::with_gas_price(5, 1.into());
+        let mut funder = Sender::random().unwrap();
+        let senders: Vec<_> = (0..senders).map(|_| Sender::random().unwrap()).collect();
+        let (setup, mix) = prepare_mix(scenarios, &mut funder, &senders, &chain).unwrap();
+
+        (funder, setup, mix)
+    }
+
+    fn weighted(weight: u32, kind: ScenarioKind) -> WeightedScenario {
+        WeightedScenario {
+            weight,
+            scenario: kind.into(),
+        }
+    }
+
+    #[test]
+    fn storage_contract_copies_its_runtime() {
+        let code = hex_decode(STORAGE_CONTRACT_CODE).unwrap();
+
+        // PUSH1 size DUP1 PUSH1 offset PUSH1 0 CODECOPY PUSH1 0 RETURN
+        let (size, offset) = (code[1] as usize, code[4] as usize);
+        assert_eq!(&code[..offset], &[
+            0x60,
+            size as u8,
+            0x80,
+            0x60,
+            offset as u8,
+            0x60,
+            0x00,
+            0x39,
+            0x60,
+            0x00,
+            0xf3
+        ]);
+        assert_eq!(code.len(), offset + size);
+
+        // both jumps of the loop land on a JUMPDEST
+        let runtime = &code[offset..];
+        assert_eq!(runtime[0x0a], 0x5b);
+        assert_eq!(runtime[0x1c], 0x5b);
+        assert_eq!(runtime.last(), Some(&0x00));
+    }
+
+    #[test]
+    fn prepare_mix_deploys_with_the_funder_nonces() {
+        let scenarios = [
+            weighted(1, ScenarioKind::Erc20Transfer),
+            weighted(1, ScenarioKind::Storage),
+            weighted(1, ScenarioKind::Erc20Transfer),
+        ];
+        let (funder, setup, mix) = prepare(&scenarios, 2);
+
+        // token deploy, grant, two mints, then the storage contract
+        assert_eq!(setup.len(), 5);
+        assert_eq!(funder.nonce, 5);
+
+        let token = TransactionAction::Call(contract_address(&funder.address, 0));
+        let storage = TransactionAction::Call(contract_address(&funder.address, 4));
+        assert_eq!(mix.calls[0].action, token);
+        assert_eq!(mix.calls[1].action, storage);
+        // the token is deployed once
+        assert_eq!(mix.calls[2].action, token);
+    }
+
+    #[test]
+    fn prepare_mix_schedules_calls_by_weight() {
+        let scenarios = [
+            weighted(3, ScenarioKind::Transfer),
+            weighted(0, ScenarioKind::Deploy),
+            weighted(1, ScenarioKind::Storage),
+        ];
+        let (funder, setup, mix) = prepare(&scenarios, 1);
+
+        assert_eq!(setup.len(), 1);
+        assert_eq!(funder.nonce, 1);
+        assert_eq!(mix.calls.len(), 3);
+        assert_eq!(mix.schedule, [0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn mix_signs_in_turn_with_the_sender_nonces() {
+        let chain = Chain::with_gas_price(5, 1.into());
+        let (_, _, mix) = prepare(&[weighted(1, ScenarioKind::Transfer)], 1);
+        let mut sender = Sender::random().unwrap();
+
+        let hashes: Vec<_> = (0..3).map(|n| mix.sign(n, &mut sender, &chain).1).collect();
+        assert_eq!(sender.nonce, 3);
+        assert_ne!(hashes[0], hashes[1]);
+        assert_ne!(hashes[1], hashes[2]);
+    }
+}
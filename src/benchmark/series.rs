@@ -0,0 +1,204 @@
+use std::{
+    fmt::Write,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{rate::RateSchedule, report::LatencyStats};
+use crate::types::Result;
+
+/// What happened in a second of a run
+#[derive(Debug, Default, Clone)]
+struct Bucket {
+    offered:             usize,
+    accepted:            usize,
+    rejected:            usize,
+    failed:              usize,
+    submit_latencies:    Vec<Duration>,
+    inclusion_latencies: Vec<Duration>,
+}
+
+/// A second of a run, where the achieved TPS falling behind the offered one
+/// shows the chain saturating
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SeriesPoint {
+    pub second:            u64,
+    /// none for a closed-loop run
+    pub target_tps:        Option<f64>,
+    /// the transactions sent in the second
+    pub offered_tps:       usize,
+    pub accepted_tps:      usize,
+    pub rejected_tps:      usize,
+    pub failed_tps:        usize,
+    /// the transactions seen in a block in the second
+    pub achieved_tps:      usize,
+    pub submit_latency:    LatencyStats,
+    pub inclusion_latency: LatencyStats,
+}
+
+impl SeriesPoint {
+    /// The point in the Prometheus text format
+    fn metrics(&self) -> String {
+        let mut metrics = String::new();
+        let mut gauge = |name: &str, labels: &str, value: f64| {
+            let _ = writeln!(metrics, "axon_benchmark_{name}{labels} {value}");
+        };
+
+        gauge("target_tps", "", self.target_tps.unwrap_or_default());
+        gauge("offered_tps", "", self.offered_tps as f64);
+        gauge("accepted_tps", "", self.accepted_tps as f64);
+        gauge("rejected_tps", "", self.rejected_tps as f64);
+        gauge("failed_tps", "", self.failed_tps as f64);
+        gauge("achieved_tps", "", self.achieved_tps as f64);
+        for (quantile, submit, inclusion) in [
+            (
+                "0.5",
+                self.submit_latency.p50_ms,
+                self.inclusion_latency.p50_ms,
+            ),
+            (
+                "0.9",
+                self.submit_latency.p90_ms,
+                self.inclusion_latency.p90_ms,
+            ),
+            (
+                "0.99",
+                self.submit_latency.p99_ms,
+                self.inclusion_latency.p99_ms,
+            ),
+        ] {
+            let labels = format!("{{quantile=\"{quantile}\"}}");
+            gauge("submit_latency_ms", &labels, submit);
+            gauge("inclusion_latency_ms", &labels, inclusion);
+        }
+
+        metrics
+    }
+}
+
+/// The per-second time series of a run, by when things were observed
+pub struct Series {
+    started: Instant,
+    buckets: Mutex<Vec<Bucket>>,
+}
+
+impl Series {
+    pub fn new(started: Instant) -> Self {
+        Self {
+            started,
+            buckets: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// When the `second`th point is complete
+    pub fn closes_at(&self, second: u64) -> Instant {
+        self.started + Duration::from_secs(second + 1)
+    }
+
+    fn update(&self, at: Instant, update: impl FnOnce(&mut Bucket)) {
+        let second = at.saturating_duration_since(self.started).as_secs() as usize;
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() <= second {
+            buckets.resize(second + 1, Bucket::default());
+        }
+
+        update(&mut buckets[second]);
+    }
+
+    pub fn offered(&self, at: Instant, count: usize) {
+        self.update(at, |bucket| bucket.offered += count);
+    }
+
+    /// Record the responses of a batch submitted at `at`
+    pub fn responded(
+        &self,
+        at: Instant,
+        accepted: usize,
+        rejected: usize,
+        failed: usize,
+        latency: Duration,
+    ) {
+        self.update(at, |bucket| {
+            bucket.accepted += accepted;
+            bucket.rejected += rejected;
+            bucket.failed += failed;
+            bucket
+                .submit_latencies
+                .extend((0..accepted).map(|_| latency));
+        });
+    }
+
+    pub fn included(&self, at: Instant, latencies: impl IntoIterator<Item = Duration>) {
+        self.update(at, |bucket| bucket.inclusion_latencies.extend(latencies));
+    }
+
+    /// The `second`th point, empty if nothing happened in it
+    pub fn point(&self, second: u64, rate: Option<&RateSchedule>) -> SeriesPoint {
+        let bucket = self
+            .buckets
+            .lock()
+            .unwrap()
+            .get(second as usize)
+            .cloned()
+            .unwrap_or_default();
+
+        SeriesPoint {
+            second,
+            target_tps: rate.map(|rate| rate.target(second as f64)),
+            offered_tps: bucket.offered,
+            accepted_tps: bucket.accepted,
+            rejected_tps: bucket.rejected,
+            failed_tps: bucket.failed,
+            achieved_tps: bucket.inclusion_latencies.len(),
+            submit_latency: LatencyStats::new(&bucket.submit_latencies),
+            inclusion_latency: LatencyStats::new(&bucket.inclusion_latencies),
+        }
+    }
+
+    pub fn points(&self, rate: Option<&RateSchedule>) -> Vec<SeriesPoint> {
+        let seconds = self.buckets.lock().unwrap().len() as u64;
+
+        (0..seconds)
+            .map(|second| self.point(second, rate))
+            .collect()
+    }
+}
+
+fn run_url(pushgateway: &str, run_id: &str) -> String {
+    format!(
+        "{}/metrics/job/axon_benchmark/run/{run_id}",
+        pushgateway.trim_end_matches('/')
+    )
+}
+
+/// Push a point to a Prometheus pushgateway as the run, so that the benchmark
+/// dashboard shows the load live
+pub async fn push_point(
+    client: &reqwest::Client,
+    pushgateway: &str,
+    run_id: &str,
+    point: &SeriesPoint,
+) -> Result<()> {
+    client
+        .put(run_url(pushgateway, run_id))
+        .body(point.metrics())
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// Delete the metrics of a finished run from the pushgateway, which would
+/// otherwise keep serving its last point
+pub async fn delete_run(client: &reqwest::Client, pushgateway: &str, run_id: &str) -> Result<()> {
+    client
+        .delete(run_url(pushgateway, run_id))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -0,0 +1,335 @@
+use std::{collections::HashSet, path::Path, time::Duration};
+
+use axon_protocol::types::Metadata;
+use clap::Args;
+use colored::*;
+use log::{info, warn};
+
+use super::{
+    default_endpoint,
+    report::{block_tps, BlockStats, LatencyStats, RunReport},
+};
+use crate::{
+    axon_nodes::profile::Profile,
+    constants::{DEFAULT_PROFILE, METADATA_TEMPLATE},
+    rpc::{BlockSummary, RpcClient},
+    types::Result,
+    utils::from_json_file,
+};
+
+/// the blocks fetched in a JSON-RPC batch
+const BLOCK_BATCH_SIZE: u64 = 100;
+
+#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct VerifyArgs {
+    /// the first block to scan, defaults to the first block of the run
+    #[clap(long)]
+    from: Option<u64>,
+
+    /// the last block to scan, defaults to the last block of the run
+    #[clap(long)]
+    to: Option<u64>,
+
+    /// the id of a run of the native engine to check against the chain
+    #[clap(short, long)]
+    run: Option<String>,
+
+    /// the largest difference in percent between the TPS of the run and the
+    /// TPS committed by the chain
+    #[clap(long, default_value = "10")]
+    max_tps_difference: u32,
+
+    /// the http endpoint of Axon, defaults to the first node of the profile
+    #[clap(short = 'H', long)]
+    http_endpoint: Option<String>,
+
+    /// the profile id of the nodes
+    #[clap(long, default_value = DEFAULT_PROFILE)]
+    profile: String,
+}
+
+/// What the chain committed over a range of blocks
+struct ChainStats {
+    blocks:       usize,
+    txs:          usize,
+    /// the TPS by block timestamp as a run measures it
+    tps:          f64,
+    intervals:    LatencyStats,
+    gas_used:     u64,
+    max_gas_used: u64,
+    max_txs:      usize,
+    /// the blocks at the transaction number limit
+    full_blocks:  usize,
+}
+
+/// The blocks the way a run records them
+fn block_stats(blocks: &[(u64, BlockSummary)]) -> Vec<BlockStats> {
+    blocks
+        .iter()
+        .map(|(number, block)| BlockStats {
+            number:    *number,
+            timestamp: block.timestamp.as_u64(),
+            tx_count:  block.transactions.len(),
+            gas_used:  block.gas_used.as_u64(),
+        })
+        .collect()
+}
+
+impl ChainStats {
+    fn new(blocks: &[(u64, BlockSummary)], metadata: &Metadata) -> Self {
+        let blocks = block_stats(blocks);
+        let intervals = blocks
+            .windows(2)
+            .map(|pair| Duration::from_secs(pair[1].timestamp.saturating_sub(pair[0].timestamp)))
+            .collect::<Vec<_>>();
+
+        Self {
+            blocks:       blocks.len(),
+            txs:          blocks.iter().map(|block| block.tx_count).sum(),
+            tps:          block_tps(&blocks).unwrap_or_default(),
+            intervals:    LatencyStats::new(&intervals),
+            gas_used:     blocks.iter().map(|block| block.gas_used).sum(),
+            max_gas_used: blocks
+                .iter()
+                .map(|block| block.gas_used)
+                .max()
+                .unwrap_or_default(),
+            max_txs:      blocks
+                .iter()
+                .map(|block| block.tx_count)
+                .max()
+                .unwrap_or_default(),
+            full_blocks:  blocks
+                .iter()
+                .filter(|block| block.tx_count as u64 >= metadata.tx_num_limit)
+                .count(),
+        }
+    }
+
+    fn print(&self, from: u64, to: u64, metadata: &Metadata) {
+        println!("/////////////////////////////////////////////////////");
+        println!("blocks: {from} to {to}, {} in total", self.blocks);
+        println!("committed transactions: {}", self.txs);
+        println!("TPS by block timestamp: {:.2}", self.tps);
+        println!(
+            "block interval: {}, expected {} ms",
+            self.intervals, metadata.interval
+        );
+        println!(
+            "gas used: {} in total, at most {} in a block, {:.2}% of the limit {}",
+            self.gas_used,
+            self.max_gas_used,
+            self.max_gas_used as f64 * 100.0 / metadata.gas_limit.max(1) as f64,
+            metadata.gas_limit
+        );
+        println!(
+            "transactions per block: at most {}, {:.2}% of the limit {}, {} full block(s)",
+            self.max_txs,
+            self.max_txs as f64 * 100.0 / metadata.tx_num_limit.max(1) as f64,
+            metadata.tx_num_limit,
+            self.full_blocks
+        );
+        println!("/////////////////////////////////////////////////////");
+    }
+}
+
+/// The metadata the nodes of a profile were generated with
+fn read_metadata(profile: &Profile) -> Metadata {
+    let path = Path::new(&profile.path)
+        .join("nodes")
+        .join("metadata_template.json");
+
+    from_json_file(&path).unwrap_or_else(|_| {
+        warn!(
+            "No metadata found at {}, the default one is used",
+            path.display()
+        );
+        METADATA_TEMPLATE.clone()
+    })
+}
+
+async fn fetch_blocks(rpc: &RpcClient, from: u64, to: u64) -> Result<Vec<(u64, BlockSummary)>> {
+    let mut blocks = Vec::new();
+    let mut start = from;
+
+    while start <= to {
+        let end = to.min(start + BLOCK_BATCH_SIZE - 1);
+        let summaries = rpc.block_summaries(start..=end).await?;
+
+        for (number, summary) in (start..=end).zip(summaries) {
+            let block = summary.map_err(|err| format!("Failed to get block {number}, {err}"))?;
+            blocks.push((number, block));
+        }
+        start = end + 1;
+    }
+
+    Ok(blocks)
+}
+
+/// Check what a run recorded against the blocks, returns the number of
+/// discrepancies
+///
+/// The transactions the endpoints accepted but no block committed are
+/// dropped, and the TPS of the chain is taken over the blocks of the run the
+/// same way the run took it.
+fn check_run(report: &RunReport, blocks: &[(u64, BlockSummary)], max_tps_difference: u32) -> usize {
+    let mut discrepancies = 0;
+    let mut report_discrepancy = |message: String| {
+        discrepancies += 1;
+        println!("{}", message.red());
+    };
+
+    if report.accepted_hashes.is_empty() {
+        warn!(
+            "Run {} recorded no accepted transactions, dropped ones can't be told",
+            report.id
+        );
+    } else {
+        let committed = blocks
+            .iter()
+            .flat_map(|(_, block)| block.transactions.iter())
+            .collect::<HashSet<_>>();
+        let dropped = report
+            .accepted_hashes
+            .iter()
+            .filter(|hash| !committed.contains(hash))
+            .count();
+
+        let line = format!(
+            "transactions accepted by the run: {}, committed: {}, dropped: {dropped}",
+            report.accepted_hashes.len(),
+            report.accepted_hashes.len() - dropped
+        );
+        if dropped > 0 {
+            report_discrepancy(line);
+        } else {
+            println!("{line}");
+        }
+    }
+
+    // the blocks of the run, all of them if it recorded none
+    let window = block_stats(blocks)
+        .into_iter()
+        .filter(|block| {
+            report
+                .blocks
+                .first()
+                .map_or(true, |first| block.number >= first.number)
+                && report
+                    .blocks
+                    .last()
+                    .map_or(true, |last| block.number <= last.number)
+        })
+        .collect::<Vec<_>>();
+    let chain_tps = block_tps(&window).unwrap_or_default();
+
+    let difference = (report.tps - chain_tps).abs() / chain_tps.max(f64::EPSILON) * 100.0;
+    let line = format!(
+        "TPS of the run: {:.2}, committed over its blocks: {chain_tps:.2}, difference: \
+         {difference:.1}%",
+        report.tps
+    );
+    if difference > f64::from(max_tps_difference) {
+        report_discrepancy(line);
+    } else {
+        println!("{line}");
+    }
+
+    discrepancies
+}
+
+/// Scan a range of blocks for the throughput actually committed, and check a
+/// run against it, a discrepancy fails the command
+pub async fn verify_blocks(args: VerifyArgs) -> Result<()> {
+    let VerifyArgs {
+        from,
+        to,
+        run,
+        max_tps_difference,
+        http_endpoint,
+        profile,
+    } = args;
+
+    let axon_profile = Profile::load_or_default(&profile)?;
+    let report = run.as_deref().map(RunReport::load).transpose()?;
+    let recorded = report.as_ref().map(|report| report.blocks.as_slice());
+    let first = recorded.and_then(|blocks| blocks.first().map(|block| block.number));
+    let last = recorded.and_then(|blocks| blocks.last().map(|block| block.number));
+
+    let (from, to) = match (from.or(first), to.or(last)) {
+        (Some(from), Some(to)) if from <= to => (from, to),
+        (Some(from), Some(to)) => {
+            return Err(format!("Block {from} is after block {to}").into());
+        }
+        _ => return Err("Give --from and --to, or a run with blocks to take them from".into()),
+    };
+
+    let rpc = RpcClient::new(http_endpoint.unwrap_or_else(|| default_endpoint(&axon_profile)))?;
+    let height = rpc.block_number().await?;
+    if to > height {
+        return Err(format!("Block {to} is over the height {height}").into());
+    }
+
+    info!("Scanning blocks {from} to {to} at {}", rpc.url());
+    let blocks = fetch_blocks(&rpc, from, to).await?;
+    let metadata = read_metadata(&axon_profile);
+    let stats = ChainStats::new(&blocks, &metadata);
+    stats.print(from, to, &metadata);
+
+    let report = match report {
+        Some(report) => report,
+        None => return Ok(()),
+    };
+    let discrepancies = check_run(&report, &blocks, max_tps_difference);
+    if discrepancies > 0 {
+        return Err(format!(
+            "{discrepancies} discrepancy(ies) between run {} and the chain",
+            report.id
+        )
+        .into());
+    }
+
+    info!("Run {} agrees with the chain", report.id);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axon_protocol::types::H256;
+
+    use super::*;
+
+    fn block(timestamp: u64, transactions: &[H256]) -> BlockSummary {
+        BlockSummary {
+            timestamp:    timestamp.into(),
+            gas_used:     0.into(),
+            transactions: transactions.to_vec(),
+        }
+    }
+
+    #[test]
+    fn check_run_counts_the_dropped_transactions() {
+        let hashes = (0..4).map(H256::from_low_u64_be).collect::<Vec<_>>();
+        let blocks = vec![
+            (1, block(10, &[])),
+            (2, block(11, &hashes[..2])),
+            (3, block(12, &hashes[2..3])),
+            (4, block(13, &[])),
+        ];
+        let mut report = RunReport {
+            tps: 1.5,
+            blocks: block_stats(&blocks[..3]),
+            accepted_hashes: hashes[..3].to_vec(),
+            ..Default::default()
+        };
+
+        assert_eq!(check_run(&report, &blocks, 10), 0);
+
+        report.accepted_hashes = hashes;
+        assert_eq!(check_run(&report, &blocks, 10), 1);
+
+        report.tps = 3.0;
+        assert_eq!(check_run(&report, &blocks, 10), 2);
+    }
+}
@@ -10,6 +10,23 @@ use crate::{
 
 pub const DEFAULT_AXON_NETWORK_NAME: &str = "axon-net";
 pub const DEFAULT_AXON_DATA_VOLUME: &str = "axon-data";
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub const LABEL_PROFILE: &str = "axon-cli.profile";
+pub const LABEL_ROLE: &str = "axon-cli.role";
+pub const LABEL_NODE: &str = "axon-cli.node";
+pub const LABEL_HTTP_PORT: &str = "axon-cli.http-port";
+pub const LABEL_P2P_PORT: &str = "axon-cli.p2p-port";
+pub const LABEL_COLLECTING_PORT: &str = "axon-cli.collecting-port";
+pub const LABEL_ADDRESS: &str = "axon-cli.address";
+pub const LABEL_CPU_SHARES: &str = "axon-cli.cpu-shares";
+pub const LABEL_CPUS: &str = "axon-cli.cpus";
+pub const LABEL_MEMORY: &str = "axon-cli.memory";
+pub const LABEL_ULIMITS: &str = "axon-cli.ulimits";
+pub const LABEL_RESTART_POLICY: &str = "axon-cli.restart-policy";
+pub const ROLE_NODE: &str = "node";
+pub const ROLE_BENCHMARK: &str = "benchmark";
+pub const ROLE_SNAPSHOT: &str = "snapshot";
 
 pub fn get_default_docker_uri() -> &'static str {
     match std::env::consts::OS {
@@ -45,8 +62,18 @@ lazy_static! {
         string_to_static_str(format!("{}/nodes", *DEFAULT_AXON_PATH));
     pub static ref DEFAULT_NODE_KEY_PAIRS_PATH: &'static str =
         string_to_static_str(format!("{}/key_pairs.json", *DEFAULT_NODES_PATH));
+    pub static ref DEFAULT_PROFILES_PATH: &'static str =
+        string_to_static_str(format!("{}/profiles", *DEFAULT_AXON_PATH));
+    pub static ref DEFAULT_CHAOS_PATH: &'static str =
+        string_to_static_str(format!("{}/chaos", *DEFAULT_AXON_PATH));
+    pub static ref DEFAULT_PROCESSES_PATH: &'static str =
+        string_to_static_str(format!("{}/processes", *DEFAULT_AXON_PATH));
+    pub static ref DEFAULT_SNAPSHOTS_PATH: &'static str =
+        string_to_static_str(format!("{}/snapshots", *DEFAULT_AXON_PATH));
     pub static ref DEFAULT_BENCHMARK_PATH: &'static str =
         string_to_static_str(format!("{}/benchmark", *DEFAULT_AXON_PATH));
+    pub static ref DEFAULT_BENCHMARK_RUNS_PATH: &'static str =
+        string_to_static_str(format!("{}/runs", *DEFAULT_BENCHMARK_PATH));
     pub static ref DEFAULT_APM_PATH: &'static str =
         string_to_static_str(format!("{}/apm", *DEFAULT_AXON_PATH));
     pub static ref DEFAULT_APM_MONITOR_PATH: &'static str =
@@ -123,5 +150,26 @@ lazy_static! {
 
 pub const AXON_IMAGE_NAME: &str = "hanssen0/axon";
 pub const AXON_IMAGE_TAG: &str = "a53490b";
+pub const LOCAL_AXON_IMAGE_NAME: &str = "axon";
+pub const LOCAL_AXON_IMAGE_TAG: &str = "dev";
+pub const DEFAULT_AXON_BINARY: &str = "axon";
 pub const BENCHMARK_IMAGE_NAME: &str = "zhengjianhui/axon-benchmark";
 pub const BENCHMARK_IMAGE_TAG: &str = "latest";
+pub const ARCHIVE_IMAGE_NAME: &str = "busybox";
+pub const ARCHIVE_IMAGE_TAG: &str = "latest";
+pub const SNAPSHOT_CONTAINER_NAME: &str = "axon-snapshot";
+
+pub const DEFAULT_NODE_HOST: &str = "127.0.0.1";
+pub const DOCKER_BRIDGE_ADDRESS: &str = "172.17.0.1";
+pub const DEFAULT_REMOTE_DOCKER_SOCKET: &str = "/var/run/docker.sock";
+pub const SSH_TUNNEL_TIMEOUT: u64 = 10;
+pub const RPC_TIMEOUT: u64 = 5;
+pub const HEALTH_CHECK_INTERVAL: u64 = 2;
+pub const HEALTH_CHECK_LOG_LINES: usize = 20;
+
+pub const TRANSFER_GAS_LIMIT: u64 = 21_000;
+pub const BENCHMARK_FUNDING: u64 = 1_000_000_000_000_000_000;
+pub const BENCHMARK_POLL_INTERVAL_MS: u64 = 500;
+pub const BENCHMARK_FUNDING_TIMEOUT: u64 = 60;
+pub const BENCHMARK_INCLUSION_TIMEOUT: u64 = 30;
+pub const BENCHMARK_PROGRESS_INTERVAL: u64 = 5;
@@ -1,18 +1,37 @@
-use std::{fmt::Display, path::Path};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
 
+use chrono::{DateTime, Utc};
 use docker_api::{
     docker::Docker,
     errors,
     errors::Result,
-    models::{ContainerInspect200Response, ImageBuildChunk, ImageInspect, Network},
-    opts::{ContainerCreateOpts, NetworkCreateOpts, PublishPort, PullOpts, RmContainerOpts},
-    Id,
+    models::{
+        ContainerInspect200Response, ContainerSummary, ImageBuildChunk, ImageInspect, Network,
+    },
+    opts::{ContainerCreateOpts, ContainerCreateOptsBuilder, PublishPort},
 };
-use futures::StreamExt;
+use http::StatusCode;
+use lazy_static::lazy_static;
 use log::{debug, error, info};
 use serde::ser::Serialize;
 
-use crate::constants::*;
+use crate::{
+    constants::*,
+    docker_client::{DockerClient, Endpoint, Engine},
+    types::DockerArgs,
+};
 
 fn parse_inspect_result<T>(result: Result<T>) -> Result<Option<T>> {
     match result {
@@ -29,6 +48,129 @@ fn parse_inspect_result<T>(result: Result<T>) -> Result<Option<T>> {
     }
 }
 
+pub type Labels = HashMap<String, String>;
+
+/// Labels shared by every container, network and volume created for a profile
+pub fn profile_labels(profile: impl AsRef<str>, role: impl AsRef<str>) -> Labels {
+    HashMap::from([
+        (LABEL_PROFILE.to_string(), profile.as_ref().to_string()),
+        (LABEL_ROLE.to_string(), role.as_ref().to_string()),
+    ])
+}
+
+fn is_labeled_with(existing: Option<&Labels>, labels: &Labels) -> bool {
+    existing.map_or(false, |existing| {
+        labels
+            .iter()
+            .filter(|(key, _)| key.as_str() == LABEL_PROFILE || key.as_str() == LABEL_ROLE)
+            .all(|(key, val)| existing.get(key) == Some(val))
+    })
+}
+
+/// The image with its tag, Docker takes `latest` for an image without one
+pub fn normalize_image(image: &str) -> String {
+    if image
+        .rsplit_once('/')
+        .map_or(image, |(_, name)| name)
+        .contains(':')
+    {
+        image.to_string()
+    } else {
+        format!("{image}:latest")
+    }
+}
+
+/// Parse the value of a label, `None` if it is missing or malformed
+pub fn label_value<T: FromStr>(labels: &Labels, key: &str) -> Option<T> {
+    labels.get(key).and_then(|val| val.parse().ok())
+}
+
+/// Limits and the restart policy of a container, which are recorded in its
+/// labels so that a recreated container keeps them
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ContainerResources {
+    pub cpu_shares:     Option<u32>,
+    pub cpus:           Option<f64>,
+    pub memory:         Option<u64>,
+    /// pairs of the name of a ulimit and its soft and hard value, e.g.
+    /// ("nofile", 65535)
+    pub ulimits:        Vec<(String, u64)>,
+    pub restart_policy: Option<(String, u64)>,
+}
+
+impl ContainerResources {
+    pub fn labels(&self) -> Labels {
+        let mut labels = Labels::new();
+
+        if let Some(cpu_shares) = self.cpu_shares {
+            labels.insert(LABEL_CPU_SHARES.to_string(), cpu_shares.to_string());
+        }
+        if let Some(cpus) = self.cpus {
+            labels.insert(LABEL_CPUS.to_string(), cpus.to_string());
+        }
+        if let Some(memory) = self.memory {
+            labels.insert(LABEL_MEMORY.to_string(), memory.to_string());
+        }
+        if !self.ulimits.is_empty() {
+            let ulimits = self
+                .ulimits
+                .iter()
+                .map(|(option, val)| format!("{option}={val}"))
+                .collect::<Vec<_>>();
+            labels.insert(LABEL_ULIMITS.to_string(), ulimits.join(","));
+        }
+        if let Some((policy, max_retries)) = &self.restart_policy {
+            labels.insert(
+                LABEL_RESTART_POLICY.to_string(),
+                format!("{policy}:{max_retries}"),
+            );
+        }
+
+        labels
+    }
+
+    pub fn from_labels(labels: &Labels) -> Self {
+        let ulimits = labels.get(LABEL_ULIMITS).map_or_else(Vec::new, |ulimits| {
+            ulimits
+                .split(',')
+                .filter_map(|ulimit| {
+                    let (option, val) = ulimit.split_once('=')?;
+                    Some((option.to_string(), val.parse().ok()?))
+                })
+                .collect()
+        });
+        let restart_policy = labels.get(LABEL_RESTART_POLICY).and_then(|policy| {
+            let (policy, max_retries) = policy.split_once(':')?;
+            Some((policy.to_string(), max_retries.parse().ok()?))
+        });
+
+        Self {
+            cpu_shares: label_value(labels, LABEL_CPU_SHARES),
+            cpus: label_value(labels, LABEL_CPUS),
+            memory: label_value(labels, LABEL_MEMORY),
+            ulimits,
+            restart_policy,
+        }
+    }
+
+    fn apply(&self, mut builder: ContainerCreateOptsBuilder) -> ContainerCreateOptsBuilder {
+        if let Some(cpu_shares) = self.cpu_shares {
+            builder = builder.cpu_shares(cpu_shares);
+        }
+        if let Some(cpus) = self.cpus {
+            builder = builder.cpus(cpus);
+        }
+        if let Some(memory) = self.memory {
+            builder = builder.memory(memory);
+        }
+
+        match &self.restart_policy {
+            Some((policy, max_retries)) => builder.restart_policy(policy, *max_retries),
+            None => builder.restart_policy("always", 0),
+        }
+    }
+}
+
 pub struct StartAxonArgs<
     S0: AsRef<str>,
     S1: AsRef<str>,
@@ -46,26 +188,237 @@ pub struct StartAxonArgs<
     pub port:            u32,
     pub collecting_port: u32,
     pub p2p_port:        u32,
+    pub labels:          Labels,
+    pub image:           String,
+    pub tag:             String,
+    pub resources:       ContainerResources,
+}
+
+/// The host part of a docker uri like tcp://10.0.0.2:2376 or
+/// ssh://user@host:22, `None` for unix sockets
+pub fn uri_host(uri: &str) -> Option<&str> {
+    let (scheme, rest) = uri.split_once("://")?;
+    if scheme == "unix" {
+        return None;
+    }
+
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+
+    Some(host.rsplit_once(':').map_or(host, |(host, _)| host))
+}
+
+/// The host and the port of a docker uri like tcp://10.0.0.2:2376
+fn host_port(uri: &str) -> crate::types::Result<(String, u16)> {
+    let host = uri_host(uri).ok_or_else(|| format!("No host in {uri}"))?;
+    let port = uri
+        .split_once("://")
+        .and_then(|(_, rest)| rest.split('/').next())
+        .and_then(|authority| authority.rsplit_once(':'))
+        .map(|(_, port)| port)
+        .ok_or_else(|| format!("No port in {uri}"))?;
+
+    Ok((
+        host.trim_matches(|c| c == '[' || c == ']').to_string(),
+        port.parse()?,
+    ))
+}
+
+fn is_loopback(host: &str) -> bool {
+    host == "localhost"
+        || host
+            .trim_matches(|c| c == '[' || c == ']')
+            .parse::<IpAddr>()
+            .map_or(false, |ip| ip.is_loopback())
+}
+
+/// Whether a Docker daemon runs on this machine, so that the ports it
+/// publishes can be checked here
+pub fn is_local_docker_uri(uri: &str) -> bool {
+    uri_host(uri).map_or(true, is_loopback)
+}
+
+/// The number of ssh tunnels opened by this process, to give each one a
+/// socket of its own
+static SSH_TUNNELS: AtomicUsize = AtomicUsize::new(0);
+
+/// An ssh process forwarding a local socket to the Docker socket of a remote
+/// host, killed when dropped
+struct SshTunnel {
+    child:  Child,
+    socket: PathBuf,
+}
+
+impl SshTunnel {
+    /// Open a tunnel for a uri like ssh://user@host:22/var/run/docker.sock
+    async fn open(uri: &str) -> crate::types::Result<Self> {
+        let rest = uri.trim_start_matches("ssh://");
+        let (authority, remote_socket) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, DEFAULT_REMOTE_DOCKER_SOCKET),
+        };
+        let (destination, port) = match authority.rsplit_once(':') {
+            Some((destination, port)) => (destination, Some(port)),
+            None => (authority, None),
+        };
+
+        let socket = std::env::temp_dir().join(format!(
+            "axon-cli-docker-{}-{}.sock",
+            std::process::id(),
+            SSH_TUNNELS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_file(&socket);
+
+        let mut command = Command::new("ssh");
+        command
+            .args(["-nNT", "-o", "ExitOnForwardFailure=yes", "-L"])
+            .arg(format!("{}:{}", socket.to_str().unwrap(), remote_socket))
+            .arg(destination)
+            .stdin(Stdio::null());
+        if let Some(port) = port {
+            command.args(["-p", port]);
+        }
+
+        info!("Opening ssh tunnel to {}...", destination);
+        let mut tunnel = Self {
+            child: command.spawn()?,
+            socket,
+        };
+
+        let deadline = Instant::now() + Duration::from_secs(SSH_TUNNEL_TIMEOUT);
+        while !tunnel.socket.exists() {
+            if let Some(status) = tunnel.child.try_wait()? {
+                return Err(format!("ssh to {destination} exited with {status}").into());
+            }
+            if Instant::now() >= deadline {
+                return Err(format!("Timed out opening ssh tunnel to {destination}").into());
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        Ok(tunnel)
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_file(&self.socket);
+    }
+}
+
+/// Makes clients for a uri instead of connecting to a daemon
+pub type Connector = Box<dyn Fn() -> Box<dyn DockerClient> + Send + Sync>;
+
+lazy_static! {
+    static ref CONNECTORS: Mutex<HashMap<String, Connector>> = Mutex::new(HashMap::new());
 }
 
 pub struct DockerApi {
-    docker: Docker,
+    docker:  Box<dyn DockerClient>,
+    _tunnel: Option<SshTunnel>,
 }
 
 impl DockerApi {
-    pub fn new(uri: String) -> Result<Self> {
+    /// Work through a client which is already connected
+    pub fn with_client(docker: Box<dyn DockerClient>) -> Self {
+        Self {
+            docker,
+            _tunnel: None,
+        }
+    }
+
+    /// Connect to the client of `connector` for `uri`, e.g. a daemon in memory
+    #[cfg(test)]
+    pub fn register_connector(uri: &str, connector: Connector) {
+        CONNECTORS
+            .lock()
+            .unwrap()
+            .insert(uri.to_string(), connector);
+    }
+
+    /// Connect to a unix socket, a TCP endpoint optionally secured by TLS, or
+    /// a remote socket through ssh
+    pub async fn new(args: DockerArgs) -> crate::types::Result<Self> {
+        let DockerArgs {
+            docker_uri,
+            tls_cert_path,
+            allow_insecure,
+        } = args;
+
+        let client = CONNECTORS
+            .lock()
+            .unwrap()
+            .get(&docker_uri)
+            .map(|connector| connector());
+        if let Some(client) = client {
+            return Ok(Self::with_client(client));
+        }
+
+        if docker_uri.starts_with("ssh://") {
+            let tunnel = SshTunnel::open(&docker_uri).await?;
+
+            let docker = Docker::new(format!("unix://{}", tunnel.socket.to_str().unwrap()))?;
+
+            return Ok(Self {
+                docker:  Box::new(Engine::new(docker, Endpoint::Unix(tunnel.socket.clone()))),
+                _tunnel: Some(tunnel),
+            });
+        }
+
+        if let Some(cert_path) = tls_cert_path {
+            // the certificates follow the layout of DOCKER_CERT_PATH, i.e.
+            // ca.pem, cert.pem and key.pem
+            let (host, port) = host_port(&docker_uri)?;
+            let docker = Docker::tls(
+                docker_uri.replacen("tcp://", "https://", 1),
+                &cert_path,
+                true,
+            )?;
+
+            return Ok(Self {
+                docker:  Box::new(Engine::new(
+                    docker,
+                    Endpoint::Tls(host, port, PathBuf::from(cert_path)),
+                )),
+                _tunnel: None,
+            });
+        }
+
+        if let Some(host) = uri_host(&docker_uri) {
+            if !is_loopback(host) && !allow_insecure {
+                return Err(format!(
+                    "Refusing to connect to {docker_uri} without authentication, use \"--tls-cert-path\", an ssh:// uri or \"--allow-insecure\""
+                )
+                .into());
+            }
+        }
+
+        let endpoint = match docker_uri.strip_prefix("unix://") {
+            Some(socket) => Endpoint::Unix(PathBuf::from(socket)),
+            None => {
+                let (host, port) = host_port(&docker_uri)?;
+                Endpoint::Tcp(host, port)
+            }
+        };
+
         Ok(Self {
-            docker: Docker::new(uri)?,
+            docker:  Box::new(Engine::new(Docker::new(&docker_uri)?, endpoint)),
+            _tunnel: None,
         })
     }
 
-    pub async fn find_network(&self, name: impl Into<Id>) -> Result<Option<Network>> {
-        let inspect = self.docker.networks().get(name).inspect().await;
+    pub async fn find_network(&self, name: impl AsRef<str>) -> Result<Option<Network>> {
+        let inspect = self.docker.inspect_network(name.as_ref()).await;
 
         parse_inspect_result(inspect)
     }
 
-    pub async fn ensure_network(&self, name: impl AsRef<str>) -> Result<()> {
+    pub async fn ensure_network(&self, name: impl AsRef<str>, labels: &Labels) -> Result<()> {
         debug!("Checking for network {}...", name.as_ref());
         let network = self.find_network(name.as_ref()).await?;
 
@@ -79,25 +432,35 @@ impl DockerApi {
         }
 
         info!("Network {} does't exist, creating...", name.as_ref());
-        let id = self
-            .docker
-            .networks()
-            .create(&NetworkCreateOpts::builder(name.as_ref()).build())
-            .await?
-            .id()
-            .to_string();
+        let id = self.docker.create_network(name.as_ref(), labels).await?;
         info!("Network {} has been created, id: {}", name.as_ref(), id);
 
         Ok(())
     }
 
-    pub async fn find_image(&self, name: impl Into<Id>) -> Result<Option<ImageInspect>> {
-        let inspect = self.docker.images().get(name).inspect().await;
+    pub async fn ensure_volume(&self, name: impl AsRef<str>, labels: &Labels) -> Result<()> {
+        debug!("Checking for volume {}...", name.as_ref());
+        let inspect = self.docker.inspect_volume(name.as_ref()).await;
+
+        if parse_inspect_result(inspect)?.is_some() {
+            debug!("Volume {} is existed", name.as_ref());
+            return Ok(());
+        }
+
+        info!("Volume {} does't exist, creating...", name.as_ref());
+        self.docker.create_volume(name.as_ref(), labels).await?;
+        info!("Volume {} has been created", name.as_ref());
+
+        Ok(())
+    }
+
+    pub async fn find_image(&self, name: impl AsRef<str>) -> Result<Option<ImageInspect>> {
+        let inspect = self.docker.inspect_image(name.as_ref()).await;
 
         parse_inspect_result(inspect)
     }
 
-    async fn ensure_image<S0: Serialize + Display, S1: Serialize + Display>(
+    pub async fn ensure_image<S0: Serialize + Display, S1: Serialize + Display>(
         &self,
         name: S0,
         tag: S1,
@@ -116,42 +479,83 @@ impl DockerApi {
         }
 
         info!("Image {} does't exist, pulling...", image_name);
-        let opts = PullOpts::builder().image(name).tag(tag).build();
-        let images = self.docker.images();
-        let mut pulling = images.pull(&opts);
-
-        while let Some(chunk) = pulling.next().await {
-            match chunk? {
-                ImageBuildChunk::PullStatus {
-                    status,
-                    id,
-                    progress,
-                    ..
-                } => {
-                    if progress.is_none() {
-                        info!("{} {}", id.unwrap_or_else(|| "".to_string()), status);
+        self.docker
+            .pull_image(&name.to_string(), &tag.to_string(), &mut |chunk| {
+                match chunk {
+                    ImageBuildChunk::PullStatus {
+                        status,
+                        id,
+                        progress,
+                        ..
+                    } => {
+                        if progress.is_none() {
+                            info!("{} {}", id.unwrap_or_else(|| "".to_string()), status);
+                        }
                     }
-                }
-                ImageBuildChunk::Update { stream } => {
-                    info!("Pulling image {} {}...", image_name, stream);
-                }
-                ImageBuildChunk::Digest { aux } => {
-                    info!("Pulling image {} aux: {}...", image_name, aux.id);
-                }
-                ImageBuildChunk::Error { error, .. } => {
-                    error!("Pulling image {} {}...", image_name, error);
-                }
-            };
-        }
+                    ImageBuildChunk::Update { stream } => {
+                        info!("Pulling image {} {}...", image_name, stream);
+                    }
+                    ImageBuildChunk::Digest { aux } => {
+                        info!("Pulling image {} aux: {}...", image_name, aux.id);
+                    }
+                    ImageBuildChunk::Error { error, .. } => {
+                        error!("Pulling image {} {}...", image_name, error);
+                    }
+                };
+            })
+            .await
+    }
 
-        Ok(())
+    /// Build an image from a local source tree through the build endpoint,
+    /// returns whether the build succeeded
+    pub async fn build_image(
+        &self,
+        src: impl AsRef<Path>,
+        dockerfile: impl AsRef<str>,
+        name: impl AsRef<str>,
+        tag: impl AsRef<str>,
+    ) -> Result<bool> {
+        let image_name = format!("{}:{}", name.as_ref(), tag.as_ref());
+
+        info!(
+            "Building image {} from {}...",
+            image_name,
+            src.as_ref().display()
+        );
+        let mut succeeded = true;
+        self.docker
+            .build_image(
+                src.as_ref(),
+                dockerfile.as_ref(),
+                &image_name,
+                &mut |chunk| {
+                    match chunk {
+                        ImageBuildChunk::Update { stream } => {
+                            print!("{}", stream);
+                        }
+                        ImageBuildChunk::PullStatus { status, id, .. } => {
+                            info!("{} {}", id.unwrap_or_else(|| "".to_string()), status);
+                        }
+                        ImageBuildChunk::Digest { aux } => {
+                            info!("Built image {} id: {}", image_name, aux.id);
+                        }
+                        ImageBuildChunk::Error { error, .. } => {
+                            error!("Building image {} {}", image_name, error);
+                            succeeded = false;
+                        }
+                    };
+                },
+            )
+            .await?;
+
+        Ok(succeeded)
     }
 
     pub async fn find_container(
         &self,
-        name: impl Into<Id>,
+        name: impl AsRef<str>,
     ) -> Result<Option<ContainerInspect200Response>> {
-        let inspect = self.docker.containers().get(name).inspect().await;
+        let inspect = self.docker.inspect_container(name.as_ref()).await;
 
         parse_inspect_result(inspect)
     }
@@ -191,14 +595,8 @@ impl DockerApi {
             }
         }
 
-        let opts = RmContainerOpts::builder().force(force).build();
-
         info!("Removing container {}, id: {}...", name.as_ref(), id);
-        self.docker
-            .containers()
-            .get(Id::from(name.as_ref()))
-            .remove(&opts)
-            .await?;
+        self.docker.remove_container(name.as_ref(), force).await?;
         info!("Removed container {}, id: {}", name.as_ref(), id);
 
         Ok(())
@@ -236,11 +634,7 @@ impl DockerApi {
         }
 
         info!("Stopping container {}, id: {}...", name.as_ref(), id);
-        self.docker
-            .containers()
-            .get(name.as_ref())
-            .stop(None)
-            .await?;
+        self.docker.stop_container(name.as_ref()).await?;
         info!("Stopped container {}, id: {}", name.as_ref(), id);
 
         Ok(())
@@ -256,44 +650,253 @@ impl DockerApi {
             .collect::<Result<()>>()
     }
 
+    pub async fn start_one_container(&self, name: impl AsRef<str>) -> Result<()> {
+        self.docker.start_container(name.as_ref()).await?;
+        info!("Started container {}", name.as_ref());
+
+        Ok(())
+    }
+
+    pub async fn restart_one_container(&self, name: impl AsRef<str>) -> Result<()> {
+        self.docker.restart_container(name.as_ref()).await?;
+        info!("Restarted container {}", name.as_ref());
+
+        Ok(())
+    }
+
+    pub async fn kill_one_container(&self, name: impl AsRef<str>) -> Result<()> {
+        self.docker.kill_container(name.as_ref()).await?;
+        info!("Killed container {}", name.as_ref());
+
+        Ok(())
+    }
+
+    pub async fn pause_one_container(&self, name: impl AsRef<str>) -> Result<()> {
+        self.docker.pause_container(name.as_ref()).await?;
+        info!("Paused container {}", name.as_ref());
+
+        Ok(())
+    }
+
+    pub async fn unpause_one_container(&self, name: impl AsRef<str>) -> Result<()> {
+        self.docker.unpause_container(name.as_ref()).await?;
+        info!("Unpaused container {}", name.as_ref());
+
+        Ok(())
+    }
+
+    pub async fn connect_network(
+        &self,
+        network: impl AsRef<str>,
+        name: impl AsRef<str>,
+    ) -> Result<()> {
+        self.docker
+            .connect_network(network.as_ref(), name.as_ref())
+            .await?;
+        info!(
+            "Connected container {} to network {}",
+            name.as_ref(),
+            network.as_ref()
+        );
+
+        Ok(())
+    }
+
+    pub async fn disconnect_network(
+        &self,
+        network: impl AsRef<str>,
+        name: impl AsRef<str>,
+    ) -> Result<()> {
+        self.docker
+            .disconnect_network(network.as_ref(), name.as_ref())
+            .await?;
+        info!(
+            "Disconnected container {} from network {}",
+            name.as_ref(),
+            network.as_ref()
+        );
+
+        Ok(())
+    }
+
+    /// Run a command inside a running container and collect its stdout and
+    /// stderr, privileged commands are allowed to e.g. change the network
+    /// settings of the container
+    pub async fn exec<S: AsRef<str>>(
+        &self,
+        name: impl AsRef<str>,
+        cmd: &[S],
+        privileged: bool,
+    ) -> Result<(i64, String)> {
+        let cmd = cmd
+            .iter()
+            .map(|arg| arg.as_ref().to_string())
+            .collect::<Vec<_>>();
+        let (exit_code, output) = self.docker.exec(name.as_ref(), &cmd, privileged).await?;
+
+        Ok((exit_code, String::from_utf8_lossy(&output).to_string()))
+    }
+
+    /// Run a command inside a running container with a TTY of `size` in rows
+    /// and columns attached to the terminal, returns its exit code
+    pub async fn exec_tty<S: AsRef<str>>(
+        &self,
+        name: impl AsRef<str>,
+        cmd: &[S],
+        size: Option<(u16, u16)>,
+    ) -> Result<i64> {
+        let cmd = cmd
+            .iter()
+            .map(|arg| arg.as_ref().to_string())
+            .collect::<Vec<_>>();
+
+        self.docker.exec_tty(name.as_ref(), &cmd, size).await
+    }
+
     pub async fn ensure_container_running(
         &self,
         image: impl AsRef<str>,
         tag: impl AsRef<str>,
         name: impl AsRef<str>,
+        labels: &Labels,
+        ulimits: &[(String, u64)],
         get_opts: impl FnOnce() -> ContainerCreateOpts,
     ) -> Result<()> {
         self.ensure_image(image.as_ref(), tag.as_ref()).await?;
 
         let container = self.find_container(name.as_ref()).await?;
 
-        let id = if let Some(container) = container {
-            let id = container.id.unwrap_or_else(|| "".to_string());
-            debug!("Container {} is existed, id: {}", name.as_ref(), id);
+        let image_name = format!("{}:{}", image.as_ref(), tag.as_ref());
+        let existing = match container {
+            Some(container) => {
+                let id = container.id.unwrap_or_else(|| "".to_string());
+                debug!("Container {} is existed, id: {}", name.as_ref(), id);
 
-            if let Some(state) = container.state {
-                if state.running == Some(true) {
+                // the nodes of another profile would be taken for the ones of
+                // this profile otherwise
+                let existing_labels = container
+                    .config
+                    .as_ref()
+                    .and_then(|config| config.labels.as_ref());
+                if !is_labeled_with(existing_labels, labels) {
+                    return Err(errors::Error::Fault {
+                        code:    StatusCode::CONFLICT,
+                        message: format!(
+                            "Container {} is not managed by profile {}, id: {}",
+                            name.as_ref(),
+                            labels
+                                .get(LABEL_PROFILE)
+                                .map_or("", |profile| profile.as_str()),
+                            id
+                        ),
+                    });
+                }
+
+                let running = container
+                    .state
+                    .as_ref()
+                    .and_then(|state| state.running)
+                    .unwrap_or(false);
+                let current_image = container
+                    .config
+                    .as_ref()
+                    .and_then(|config| config.image.clone())
+                    .unwrap_or_default();
+
+                if normalize_image(&current_image) != normalize_image(&image_name) {
+                    if running {
+                        return Err(errors::Error::Fault {
+                            code:    StatusCode::CONFLICT,
+                            message: format!(
+                                "Container {} is running image {current_image} rather than \
+                                 {image_name}, see \"axon upgrade\"",
+                                name.as_ref()
+                            ),
+                        });
+                    }
+
+                    info!(
+                        "Container {} was created from image {current_image}, recreating it \
+                         from {image_name}...",
+                        name.as_ref()
+                    );
+                    self.docker.remove_container(name.as_ref(), false).await?;
+                    None
+                } else if running {
                     error!("Container {} is already running, id: {}", name.as_ref(), id);
                     return Ok(());
+                } else {
+                    Some(id)
                 }
             }
+            None => {
+                info!("Container {} does't exist, creating...", name.as_ref());
+                None
+            }
+        };
 
-            id
-        } else {
-            info!("Container {} does't exist, creating...", name.as_ref());
-            let container = self.docker.containers().create(&get_opts()).await?;
-            let id = container.id().to_string();
-            info!("Container {} has been created, id: {}", name.as_ref(), id);
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                let id = self.docker.create_container(&get_opts(), ulimits).await?;
+                info!("Container {} has been created, id: {}", name.as_ref(), id);
 
-            id
+                id
+            }
         };
 
-        self.docker.containers().get(name.as_ref()).start().await?;
+        self.docker.start_container(name.as_ref()).await?;
         info!("Container {} has started, id: {}", name.as_ref(), id);
 
         Ok(())
     }
 
+    /// List containers, including stopped ones, carrying all the given labels
+    pub async fn list_containers(&self, labels: &Labels) -> Result<Vec<ContainerSummary>> {
+        self.docker.list_containers(labels).await
+    }
+
+    /// Run a one-off container until it exits and remove it afterwards,
+    /// returns whether it exited successfully
+    pub async fn run_once(
+        &self,
+        image: impl AsRef<str>,
+        tag: impl AsRef<str>,
+        name: impl AsRef<str>,
+        get_opts: impl FnOnce() -> ContainerCreateOpts,
+    ) -> Result<bool> {
+        self.ensure_image(image.as_ref(), tag.as_ref()).await?;
+
+        if self.find_container(name.as_ref()).await?.is_some() {
+            debug!("Removing leftover container {}...", name.as_ref());
+            self.remove_one_container(name.as_ref(), true).await?;
+        }
+
+        let id = self.docker.create_container(&get_opts(), &[]).await?;
+        info!("Running container {}, id: {}...", name.as_ref(), id);
+        self.docker.start_container(name.as_ref()).await?;
+        let status_code = self.docker.wait_container(name.as_ref()).await?;
+
+        let succeeded = status_code == 0;
+        if !succeeded {
+            error!(
+                "Container {} exited with code {}",
+                name.as_ref(),
+                status_code
+            );
+            for line in self
+                .tail_container_logs(name.as_ref(), HEALTH_CHECK_LOG_LINES)
+                .await?
+            {
+                println!("{}", line);
+            }
+        }
+
+        self.docker.remove_container(name.as_ref(), true).await?;
+
+        Ok(succeeded)
+    }
+
     pub async fn inspect_one_container(&self, name: impl AsRef<str>) -> Result<()> {
         let container = self.get_container(name.as_ref()).await?;
 
@@ -315,6 +918,22 @@ impl DockerApi {
         Ok(())
     }
 
+    pub async fn container_status(&self, name: impl AsRef<str>) -> Result<Option<String>> {
+        let container = self.find_container(name.as_ref()).await?;
+
+        Ok(container.map(|container| {
+            container
+                .state
+                .and_then(|state| state.status)
+                .unwrap_or_else(|| "".to_string())
+        }))
+    }
+
+    /// Wait for a container to exit, returns its exit code
+    pub async fn wait_one_container(&self, name: impl AsRef<str>) -> Result<i64> {
+        self.docker.wait_container(name.as_ref()).await
+    }
+
     pub async fn inspect_containers<S: AsRef<str>, T: IntoIterator<Item = S>>(
         &self,
         names: T,
@@ -329,8 +948,90 @@ impl DockerApi {
         .collect::<Result<()>>()
     }
 
+    pub async fn tail_container_logs(
+        &self,
+        name: impl AsRef<str>,
+        lines: usize,
+    ) -> Result<Vec<String>> {
+        let mut output = Vec::new();
+        self.docker
+            .container_logs(
+                name.as_ref(),
+                Some(lines),
+                None,
+                false,
+                false,
+                &mut |chunk| output.extend(chunk),
+            )
+            .await?;
+
+        Ok(String::from_utf8_lossy(&output)
+            .lines()
+            .map(|line| line.to_string())
+            .collect())
+    }
+
+    /// Read the logs of a container line by line, each line is prefixed with
+    /// the RFC 3339 timestamp added by Docker, the daemon skips the lines
+    /// before `since`
+    pub async fn stream_container_logs(
+        &self,
+        name: impl AsRef<str>,
+        since: Option<DateTime<Utc>>,
+        follow: bool,
+        mut on_line: impl FnMut(String) + Send,
+    ) -> Result<()> {
+        let mut buffer = Vec::new();
+        self.docker
+            .container_logs(name.as_ref(), None, since, follow, true, &mut |chunk| {
+                buffer.extend(chunk);
+
+                while let Some(pos) = buffer.iter().position(|byte| *byte == b'\n') {
+                    let line = buffer.drain(..=pos).collect::<Vec<_>>();
+                    on_line(String::from_utf8_lossy(&line).trim_end().to_string());
+                }
+            })
+            .await?;
+
+        if !buffer.is_empty() {
+            on_line(String::from_utf8_lossy(&buffer).trim_end().to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Remove a volume only if it carries all the given labels
+    pub async fn remove_labeled_volume(
+        &self,
+        name: impl AsRef<str>,
+        labels: &Labels,
+    ) -> Result<()> {
+        let labeled = self.docker.list_volumes(labels).await?;
+        if labeled.iter().any(|volume| volume == name.as_ref()) {
+            return self.remove_one_volume(name).await;
+        }
+
+        let inspect = self.docker.inspect_volume(name.as_ref()).await;
+        match parse_inspect_result(inspect)? {
+            Some(_) => Err(errors::Error::Fault {
+                code:    StatusCode::CONFLICT,
+                message: format!(
+                    "Volume {} is not managed by profile {}",
+                    name.as_ref(),
+                    labels
+                        .get(LABEL_PROFILE)
+                        .map_or("", |profile| profile.as_str()),
+                ),
+            }),
+            None => {
+                error!("Volume {} doesn't exist", name.as_ref());
+                Ok(())
+            }
+        }
+    }
+
     pub async fn remove_one_volume(&self, name: impl AsRef<str>) -> Result<()> {
-        let remove = self.docker.volumes().get(name.as_ref()).delete().await;
+        let remove = self.docker.delete_volume(name.as_ref()).await;
 
         match parse_inspect_result(remove)? {
             Some(_) => {
@@ -365,10 +1066,14 @@ impl DockerApi {
             port,
             p2p_port,
             collecting_port,
+            labels,
+            image,
+            tag,
+            resources,
         } = args;
 
-        self.ensure_container_running(AXON_IMAGE_NAME, AXON_IMAGE_TAG, &name, || {
-            let cmd = [
+        self.ensure_container_running(&image, &tag, &name, &labels, &resources.ulimits, || {
+            let cmd = vec![
                 "./axon".to_string(),
                 format!("-c=/app/nodes/{}", config_path.as_ref()),
                 format!("-g=/app/nodes/{}", genesis_path.as_ref()),
@@ -382,10 +1087,13 @@ impl DockerApi {
                 format!("{}:/app/logs", logs_path.to_str().unwrap()),
             ];
 
-            ContainerCreateOpts::builder(format!("{}:{}", AXON_IMAGE_NAME, AXON_IMAGE_TAG))
+            let builder = ContainerCreateOpts::builder(format!("{}:{}", image, tag))
                 .name(name.as_ref())
-                .cmd(cmd)
-                .restart_policy("always", 0)
+                .labels(&labels)
+                .cmd(cmd);
+
+            resources
+                .apply(builder)
                 .volumes(volumes)
                 .working_dir("/app")
                 .network_mode(network.as_ref())
@@ -402,11 +1110,16 @@ impl DockerApi {
         path: impl AsRef<Path>,
         http_endpoint: impl AsRef<str>,
         network: impl AsRef<str>,
+        profile: impl AsRef<str>,
     ) -> Result<()> {
+        let labels = profile_labels(profile, ROLE_BENCHMARK);
+
         self.ensure_container_running(
             BENCHMARK_IMAGE_NAME,
             BENCHMARK_IMAGE_TAG,
             "benchmark",
+            &labels,
+            &[],
             || {
                 let config_path = path.as_ref().join("config.json");
                 let logs_path = path.as_ref().join("logs");
@@ -415,16 +1128,19 @@ impl DockerApi {
                     format!("{}:/benchmark/logs", logs_path.to_str().unwrap()),
                 ];
 
-                ContainerCreateOpts::builder(BENCHMARK_IMAGE_NAME)
-                    .name("benchmark")
-                    .cmd([
-                        "node",
-                        "index.js",
-                        &format!("--http_endpoint={}", http_endpoint.as_ref()),
-                    ])
-                    .volumes(vols)
-                    .network_mode(network.as_ref())
-                    .build()
+                ContainerCreateOpts::builder(format!(
+                    "{BENCHMARK_IMAGE_NAME}:{BENCHMARK_IMAGE_TAG}"
+                ))
+                .name("benchmark")
+                .labels(&labels)
+                .cmd([
+                    "node",
+                    "index.js",
+                    &format!("--http_endpoint={}", http_endpoint.as_ref()),
+                ])
+                .volumes(vols)
+                .network_mode(network.as_ref())
+                .build()
             },
         )
         .await
@@ -0,0 +1,597 @@
+use std::{
+    io::{Read, Write},
+    ops::Deref,
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use docker_api::{
+    conn::TtyChunk,
+    docker::Docker,
+    errors::{Error, Result},
+    exec::Exec,
+    models::{
+        ContainerInspect200Response, ContainerSummary, ImageBuildChunk, ImageInspect, Network,
+    },
+    opts::{
+        ContainerConnectionOpts, ContainerCreateOpts, ContainerDisconnectionOpts, ContainerFilter,
+        ContainerListOpts, ExecCreateOpts, ImageBuildOpts, LogsOpts, NetworkCreateOpts, PullOpts,
+        RmContainerOpts, VolumeCreateOpts, VolumeFilter, VolumeListOpts,
+    },
+};
+use futures::StreamExt;
+use hyper::{header, Body, Method, Request, Response, StatusCode};
+use openssl::ssl::{SslConnector, SslFiletype, SslMethod};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpStream, UnixStream},
+};
+use tokio_openssl::SslStream;
+
+use crate::docker::Labels;
+
+fn tty_chunk_bytes(chunk: TtyChunk) -> Vec<u8> {
+    match chunk {
+        TtyChunk::StdIn(bytes) | TtyChunk::StdOut(bytes) | TtyChunk::StdErr(bytes) => bytes,
+    }
+}
+
+fn io_error(err: impl std::fmt::Display) -> Error {
+    Error::IO(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        err.to_string(),
+    ))
+}
+
+/// The error of a failed request to the Engine API, which has the message in
+/// a JSON body
+fn fault(code: StatusCode, body: &[u8]) -> Error {
+    let message: Value = serde_json::from_slice(body).unwrap_or_default();
+
+    Error::Fault {
+        code,
+        message: message["message"]
+            .as_str()
+            .map_or_else(|| String::from_utf8_lossy(body).to_string(), str::to_string),
+    }
+}
+
+/// Send a request over a connection to the Engine API
+async fn send<S>(stream: S, request: Request<Body>) -> Result<Response<Body>>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::handshake(stream)
+        .await
+        .map_err(io_error)?;
+    // upgrades keep the connection for the attached streams of exec
+    tokio::spawn(connection.with_upgrades());
+
+    sender.send_request(request).await.map_err(io_error)
+}
+
+/// Where the Engine API of a daemon listens
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    Unix(PathBuf),
+    /// the host and the port
+    Tcp(String, u16),
+    /// the host, the port and the directory of ca.pem, cert.pem and key.pem
+    Tls(String, u16, PathBuf),
+}
+
+/// docker-api, with the endpoint of the daemon for the requests it can't
+/// build
+pub struct Engine {
+    docker:   Docker,
+    endpoint: Endpoint,
+}
+
+impl Engine {
+    pub fn new(docker: Docker, endpoint: Endpoint) -> Self {
+        Self { docker, endpoint }
+    }
+
+    /// Send a request to the Engine API over a new connection
+    async fn request(&self, request: Request<Body>) -> Result<Response<Body>> {
+        match &self.endpoint {
+            Endpoint::Unix(socket) => send(UnixStream::connect(socket).await?, request).await,
+            Endpoint::Tcp(host, port) => {
+                send(TcpStream::connect((host.as_str(), *port)).await?, request).await
+            }
+            Endpoint::Tls(host, port, cert_path) => {
+                let mut builder = SslConnector::builder(SslMethod::tls()).map_err(io_error)?;
+                builder
+                    .set_ca_file(cert_path.join("ca.pem"))
+                    .map_err(io_error)?;
+                builder
+                    .set_certificate_file(cert_path.join("cert.pem"), SslFiletype::PEM)
+                    .map_err(io_error)?;
+                builder
+                    .set_private_key_file(cert_path.join("key.pem"), SslFiletype::PEM)
+                    .map_err(io_error)?;
+                let ssl = builder
+                    .build()
+                    .configure()
+                    .and_then(|config| config.into_ssl(host))
+                    .map_err(io_error)?;
+
+                let tcp = TcpStream::connect((host.as_str(), *port)).await?;
+                let mut stream = SslStream::new(ssl, tcp).map_err(io_error)?;
+                Pin::new(&mut stream).connect().await.map_err(io_error)?;
+
+                send(stream, request).await
+            }
+        }
+    }
+
+    /// Send a request with an optional JSON body to the Engine API, returns
+    /// the JSON response, which is null if there is none
+    async fn request_json(&self, method: Method, path: &str, body: Option<Value>) -> Result<Value> {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::HOST, "docker")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .map_err(io_error)?;
+        let response = self.request(request).await?;
+
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(io_error)?;
+        if !status.is_success() {
+            return Err(fault(status, &bytes));
+        }
+        if bytes.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+impl Deref for Engine {
+    type Target = Docker;
+
+    fn deref(&self) -> &Docker {
+        &self.docker
+    }
+}
+
+/// The Docker operations `DockerApi` is built on, so that the lifecycle of
+/// containers can run against a fake daemon in tests
+///
+/// Objects are addressed by name, and a missing one is reported as the 404
+/// fault of the Docker engine API.
+#[async_trait]
+pub trait DockerClient: Send + Sync {
+    async fn inspect_network(&self, name: &str) -> Result<Network>;
+
+    /// Create a network, returns its id
+    async fn create_network(&self, name: &str, labels: &Labels) -> Result<String>;
+
+    async fn connect_network(&self, network: &str, container: &str) -> Result<()>;
+
+    async fn disconnect_network(&self, network: &str, container: &str) -> Result<()>;
+
+    async fn inspect_volume(&self, name: &str) -> Result<()>;
+
+    async fn create_volume(&self, name: &str, labels: &Labels) -> Result<()>;
+
+    async fn delete_volume(&self, name: &str) -> Result<()>;
+
+    /// List the names of the volumes carrying all the given labels
+    async fn list_volumes(&self, labels: &Labels) -> Result<Vec<String>>;
+
+    async fn inspect_image(&self, name: &str) -> Result<ImageInspect>;
+
+    async fn pull_image(
+        &self,
+        name: &str,
+        tag: &str,
+        on_chunk: &mut (dyn FnMut(ImageBuildChunk) + Send),
+    ) -> Result<()>;
+
+    async fn build_image(
+        &self,
+        src: &Path,
+        dockerfile: &str,
+        image: &str,
+        on_chunk: &mut (dyn FnMut(ImageBuildChunk) + Send),
+    ) -> Result<()>;
+
+    async fn inspect_container(&self, name: &str) -> Result<ContainerInspect200Response>;
+
+    /// List containers, including stopped ones, carrying all the given labels
+    async fn list_containers(&self, labels: &Labels) -> Result<Vec<ContainerSummary>>;
+
+    /// Create the container named in `opts` with the given ulimits, returns its
+    /// id
+    async fn create_container(
+        &self,
+        opts: &ContainerCreateOpts,
+        ulimits: &[(String, u64)],
+    ) -> Result<String>;
+
+    async fn start_container(&self, name: &str) -> Result<()>;
+
+    async fn stop_container(&self, name: &str) -> Result<()>;
+
+    async fn restart_container(&self, name: &str) -> Result<()>;
+
+    async fn kill_container(&self, name: &str) -> Result<()>;
+
+    async fn pause_container(&self, name: &str) -> Result<()>;
+
+    async fn unpause_container(&self, name: &str) -> Result<()>;
+
+    async fn remove_container(&self, name: &str, force: bool) -> Result<()>;
+
+    /// Wait for a container to exit, returns its exit code
+    async fn wait_container(&self, name: &str) -> Result<i64>;
+
+    /// Read the stdout and stderr of a container, the last `lines` lines only
+    /// and the ones since a time only if specified
+    async fn container_logs(
+        &self,
+        name: &str,
+        lines: Option<usize>,
+        since: Option<DateTime<Utc>>,
+        follow: bool,
+        timestamps: bool,
+        on_chunk: &mut (dyn FnMut(Vec<u8>) + Send),
+    ) -> Result<()>;
+
+    /// Run a command in a running container, returns its exit code and its
+    /// stdout and stderr
+    async fn exec(&self, name: &str, cmd: &[String], privileged: bool) -> Result<(i64, Vec<u8>)>;
+
+    /// Run a command in a running container with a TTY of `size` in rows and
+    /// columns, attached to the stdin and stdout of this process, returns its
+    /// exit code
+    async fn exec_tty(&self, name: &str, cmd: &[String], size: Option<(u16, u16)>) -> Result<i64>;
+}
+
+#[async_trait]
+impl DockerClient for Engine {
+    async fn inspect_network(&self, name: &str) -> Result<Network> {
+        self.networks().get(name).inspect().await
+    }
+
+    async fn create_network(&self, name: &str, labels: &Labels) -> Result<String> {
+        let network = self
+            .networks()
+            .create(&NetworkCreateOpts::builder(name).labels(labels).build())
+            .await?;
+
+        Ok(network.id().to_string())
+    }
+
+    async fn connect_network(&self, network: &str, container: &str) -> Result<()> {
+        self.networks()
+            .get(network)
+            .connect(&ContainerConnectionOpts::builder(container).build())
+            .await
+    }
+
+    async fn disconnect_network(&self, network: &str, container: &str) -> Result<()> {
+        self.networks()
+            .get(network)
+            .disconnect(
+                &ContainerDisconnectionOpts::builder(container)
+                    .force(true)
+                    .build(),
+            )
+            .await
+    }
+
+    async fn inspect_volume(&self, name: &str) -> Result<()> {
+        self.volumes().get(name).inspect().await?;
+
+        Ok(())
+    }
+
+    async fn create_volume(&self, name: &str, labels: &Labels) -> Result<()> {
+        self.volumes()
+            .create(
+                &VolumeCreateOpts::builder()
+                    .name(name)
+                    .labels(labels)
+                    .build(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_volume(&self, name: &str) -> Result<()> {
+        self.volumes().get(name).delete().await?;
+
+        Ok(())
+    }
+
+    async fn list_volumes(&self, labels: &Labels) -> Result<Vec<String>> {
+        let opts = VolumeListOpts::builder()
+            .filter(
+                labels
+                    .iter()
+                    .map(|(key, val)| VolumeFilter::Label(key.clone(), val.clone())),
+            )
+            .build();
+
+        Ok(self
+            .volumes()
+            .list(&opts)
+            .await?
+            .volumes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|volume| volume.name)
+            .collect())
+    }
+
+    async fn inspect_image(&self, name: &str) -> Result<ImageInspect> {
+        self.images().get(name).inspect().await
+    }
+
+    async fn pull_image(
+        &self,
+        name: &str,
+        tag: &str,
+        on_chunk: &mut (dyn FnMut(ImageBuildChunk) + Send),
+    ) -> Result<()> {
+        let opts = PullOpts::builder().image(name).tag(tag).build();
+        let images = self.images();
+        let mut pulling = images.pull(&opts);
+
+        while let Some(chunk) = pulling.next().await {
+            on_chunk(chunk?);
+        }
+
+        Ok(())
+    }
+
+    async fn build_image(
+        &self,
+        src: &Path,
+        dockerfile: &str,
+        image: &str,
+        on_chunk: &mut (dyn FnMut(ImageBuildChunk) + Send),
+    ) -> Result<()> {
+        let opts = ImageBuildOpts::builder(src)
+            .dockerfile(dockerfile)
+            .tag(image)
+            .build();
+        let images = self.images();
+        let mut building = images.build(&opts);
+
+        while let Some(chunk) = building.next().await {
+            on_chunk(chunk?);
+        }
+
+        Ok(())
+    }
+
+    async fn inspect_container(&self, name: &str) -> Result<ContainerInspect200Response> {
+        self.containers().get(name).inspect().await
+    }
+
+    async fn list_containers(&self, labels: &Labels) -> Result<Vec<ContainerSummary>> {
+        let opts = ContainerListOpts::builder()
+            .all(true)
+            .filter(
+                labels
+                    .iter()
+                    .map(|(key, val)| ContainerFilter::Label(key.clone(), val.clone())),
+            )
+            .build();
+
+        self.containers().list(&opts).await
+    }
+
+    async fn create_container(
+        &self,
+        opts: &ContainerCreateOpts,
+        ulimits: &[(String, u64)],
+    ) -> Result<String> {
+        if ulimits.is_empty() {
+            let container = self.containers().create(opts).await?;
+
+            return Ok(container.id().to_string());
+        }
+
+        // the create options of docker-api don't take ulimits
+        let mut body: Value = serde_json::from_str(&opts.serialize()?)?;
+        body["HostConfig"]["Ulimits"] = ulimits
+            .iter()
+            .map(|(name, val)| json!({ "Name": name, "Soft": val, "Hard": val }))
+            .collect();
+
+        let path = match opts.name() {
+            Some(name) => format!("/containers/create?name={name}"),
+            None => "/containers/create".to_string(),
+        };
+        let response = self.request_json(Method::POST, &path, Some(body)).await?;
+
+        Ok(response["Id"].as_str().unwrap_or_default().to_string())
+    }
+
+    async fn start_container(&self, name: &str) -> Result<()> {
+        self.containers().get(name).start().await
+    }
+
+    async fn stop_container(&self, name: &str) -> Result<()> {
+        self.containers().get(name).stop(None).await
+    }
+
+    async fn restart_container(&self, name: &str) -> Result<()> {
+        self.containers().get(name).restart(None).await
+    }
+
+    async fn kill_container(&self, name: &str) -> Result<()> {
+        self.containers().get(name).kill(None).await
+    }
+
+    async fn pause_container(&self, name: &str) -> Result<()> {
+        self.containers().get(name).pause().await
+    }
+
+    async fn unpause_container(&self, name: &str) -> Result<()> {
+        self.containers().get(name).unpause().await
+    }
+
+    async fn remove_container(&self, name: &str, force: bool) -> Result<()> {
+        self.containers()
+            .get(name)
+            .remove(&RmContainerOpts::builder().force(force).build())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn wait_container(&self, name: &str) -> Result<i64> {
+        Ok(self.containers().get(name).wait().await?.status_code)
+    }
+
+    async fn container_logs(
+        &self,
+        name: &str,
+        lines: Option<usize>,
+        since: Option<DateTime<Utc>>,
+        follow: bool,
+        timestamps: bool,
+        on_chunk: &mut (dyn FnMut(Vec<u8>) + Send),
+    ) -> Result<()> {
+        let mut builder = LogsOpts::builder()
+            .stdout(true)
+            .stderr(true)
+            .follow(follow)
+            .timestamps(timestamps);
+        if let Some(lines) = lines {
+            builder = builder.n_lines(lines);
+        }
+        if let Some(since) = since {
+            builder = builder.since(&since);
+        }
+        let opts = builder.build();
+        let container = self.containers().get(name);
+        let mut logs = container.logs(&opts);
+
+        while let Some(chunk) = logs.next().await {
+            on_chunk(tty_chunk_bytes(chunk?));
+        }
+
+        Ok(())
+    }
+
+    async fn exec(&self, name: &str, cmd: &[String], privileged: bool) -> Result<(i64, Vec<u8>)> {
+        let opts = ExecCreateOpts::builder()
+            .command(cmd.iter().map(|arg| arg.as_str()).collect::<Vec<_>>())
+            .attach_stdout(true)
+            .attach_stderr(true)
+            .privileged(privileged)
+            .build();
+        let exec = Exec::create(self.docker.clone(), name, &opts).await?;
+
+        let mut output = Vec::new();
+        let mut stream = exec.start();
+        while let Some(chunk) = stream.next().await {
+            output.extend(tty_chunk_bytes(chunk?));
+        }
+        drop(stream);
+
+        // the exit code is known once the output is drained
+        let exit_code = exec.inspect().await?.exit_code.unwrap_or_default();
+
+        Ok((exit_code as i64, output))
+    }
+
+    async fn exec_tty(&self, name: &str, cmd: &[String], size: Option<(u16, u16)>) -> Result<i64> {
+        // the exec of docker-api can't write to stdin, so the stream is
+        // upgraded by hand like the docker cli does
+        let exec = self
+            .request_json(
+                Method::POST,
+                &format!("/containers/{name}/exec"),
+                Some(json!({
+                    "AttachStdin": true,
+                    "AttachStdout": true,
+                    "AttachStderr": true,
+                    "Tty": true,
+                    "Cmd": cmd,
+                })),
+            )
+            .await?;
+        let id = exec["Id"].as_str().unwrap_or_default().to_string();
+
+        let request = Request::post(format!("/exec/{id}/start"))
+            .header(header::HOST, "docker")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CONNECTION, "Upgrade")
+            .header(header::UPGRADE, "tcp")
+            .body(Body::from(
+                json!({ "Detach": false, "Tty": true }).to_string(),
+            ))
+            .map_err(io_error)?;
+        let response = self.request(request).await?;
+        if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            let status = response.status();
+            let bytes = hyper::body::to_bytes(response.into_body())
+                .await
+                .map_err(io_error)?;
+            return Err(fault(status, &bytes));
+        }
+        let upgraded = hyper::upgrade::on(response).await.map_err(io_error)?;
+        let (mut reader, mut writer) = tokio::io::split(upgraded);
+
+        if let Some((rows, columns)) = size {
+            self.request_json(
+                Method::POST,
+                &format!("/exec/{id}/resize?h={rows}&w={columns}"),
+                None,
+            )
+            .await?;
+        }
+
+        // stdin is read by a thread of its own, since a blocking read can't be
+        // cancelled when the command exits
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+        std::thread::spawn(move || {
+            let mut buf = [0; 1024];
+            while let Ok(len @ 1..) = std::io::stdin().read(&mut buf) {
+                if sender.send(buf[..len].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+        let input = tokio::spawn(async move {
+            while let Some(bytes) = receiver.recv().await {
+                if writer.write_all(&bytes).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut buf = vec![0; 4096];
+        loop {
+            let len = reader.read(&mut buf).await?;
+            if len == 0 {
+                break;
+            }
+            std::io::stdout().write_all(&buf[..len])?;
+            std::io::stdout().flush()?;
+        }
+        input.abort();
+
+        let exec = self
+            .request_json(Method::GET, &format!("/exec/{id}/json"), None)
+            .await?;
+
+        Ok(exec["ExitCode"].as_i64().unwrap_or_default())
+    }
+}
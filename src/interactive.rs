@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use clap::{crate_version, Command};
+use clap::{crate_version, ArgMatches, Command};
 use colored::*;
 use log::error;
 use rustyline::{error::ReadlineError, Editor};
@@ -49,6 +49,43 @@ impl Interactive {
             .subcommands(subcmds)
     }
 
+    /// Run a single command given on the command line, returns whether it
+    /// succeeded
+    pub async fn run_once(&mut self, args: Vec<String>) -> bool {
+        let matches = match self.build_interactive().try_get_matches_from(args) {
+            Ok(matches) => matches,
+            // prints the help and version too
+            Err(err) => err.exit(),
+        };
+
+        self.dispatch(&matches).await
+    }
+
+    /// Run the subcommand chosen by the matches, returns whether it succeeded
+    async fn dispatch(&mut self, matches: &ArgMatches) -> bool {
+        let (name, matches) = match matches.subcommand() {
+            Some(subcommand) => subcommand,
+            None => {
+                println!("cli parse error");
+                return false;
+            }
+        };
+
+        let sub_cmd = match self.sub_cmds.get_mut(name) {
+            Some(sub_cmd) => sub_cmd,
+            None => {
+                error!("Unknown command {}", name);
+                return false;
+            }
+        };
+        if let Err(err) = sub_cmd.exec_command(matches).await {
+            error!("{}", err);
+            return false;
+        }
+
+        true
+    }
+
     pub async fn start(&mut self) {
         let mut rl = Editor::<()>::new();
         if rl.load_history(HISTORY_FILE).is_err() {
@@ -65,15 +102,7 @@ impl Interactive {
                     let app_m = parser.clone().try_get_matches_from(args);
                     match app_m {
                         Ok(matches) => {
-                            if let Some((name, matches)) = matches.subcommand() {
-                                // println!("cmd name: {}", name);
-                                let sub_cmd = self.sub_cmds.get_mut(name).unwrap();
-                                if let Err(err) = sub_cmd.exec_command(matches).await {
-                                    error!("{}", err);
-                                }
-                            } else {
-                                println!("cli parse error");
-                            }
+                            self.dispatch(&matches).await;
                         }
                         Err(err) => {
                             err.print().expect("Error writing error");
@@ -5,11 +5,16 @@ mod crosschain_tx;
 
 mod constants;
 mod docker;
+mod docker_client;
 mod interactive;
+mod rpc;
 mod sub_command;
 mod types;
 mod utils;
 
+#[cfg(test)]
+mod tests;
+
 use interactive::Interactive;
 use simplelog::{ConfigBuilder, TermLogger, TerminalMode};
 
@@ -26,5 +31,15 @@ async fn main() {
     )))
     .expect("unable to set logger");
     let mut inter = Interactive::new();
+
+    // a command on the command line runs without the prompt, and a failure
+    // becomes the exit code, e.g. for `benchmark compare` in CI
+    if std::env::args().len() > 1 {
+        if !inter.run_once(std::env::args().collect()).await {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     inter.start().await;
 }
@@ -0,0 +1,280 @@
+use std::time::Duration;
+
+use axon_protocol::types::{H160, H256, U256};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use crate::{constants::RPC_TIMEOUT, types::Result};
+
+#[derive(Deserialize, Debug)]
+struct RpcError {
+    code:    i64,
+    message: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct TxPoolStatus {
+    pending: U256,
+    queued:  U256,
+}
+
+#[derive(Deserialize, Debug)]
+struct BlockHash {
+    hash: H256,
+}
+
+/// A block with the hashes of its transactions
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockSummary {
+    pub timestamp:    U256,
+    pub gas_used:     U256,
+    pub transactions: Vec<H256>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RpcResponse<T> {
+    #[serde(default)]
+    id:     Option<usize>,
+    result: Option<T>,
+    error:  Option<RpcError>,
+}
+
+pub struct RpcClient {
+    client: reqwest::Client,
+    url:    String,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(RPC_TIMEOUT))
+                .build()?,
+            url:    url.into(),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let response: RpcResponse<T> = self
+            .client
+            .post(&self.url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(RpcError { code, message }) = response.error {
+            return Err(format!("RPC {method} failed, code: {code}, message: {message}").into());
+        }
+
+        response
+            .result
+            .ok_or_else(|| format!("RPC {method} returned no result").into())
+    }
+
+    /// Send one request per item of `params` in a single batch, a failed item
+    /// doesn't fail the others
+    pub async fn batch_call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Vec<Value>,
+    ) -> Result<Vec<std::result::Result<T, String>>> {
+        let requests = params
+            .into_iter()
+            .enumerate()
+            .map(|(id, params)| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "method": method,
+                    "params": params,
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut results = requests
+            .iter()
+            .map(|_| Err(format!("RPC {method} got no response")))
+            .collect::<Vec<_>>();
+
+        let responses: Vec<RpcResponse<T>> = self
+            .client
+            .post(&self.url)
+            .json(&requests)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // the responses of a batch may come in any order
+        for RpcResponse { id, result, error } in responses {
+            let slot = match id.and_then(|id| results.get_mut(id)) {
+                Some(slot) => slot,
+                None => continue,
+            };
+
+            *slot = match (result, error) {
+                (_, Some(RpcError { code, message })) => {
+                    Err(format!("code: {code}, message: {message}"))
+                }
+                (Some(result), None) => Ok(result),
+                (None, None) => Err(format!("RPC {method} returned no result")),
+            };
+        }
+
+        Ok(results)
+    }
+
+    pub async fn block_number(&self) -> Result<u64> {
+        Ok(self
+            .call::<U256>("eth_blockNumber", json!([]))
+            .await?
+            .as_u64())
+    }
+
+    pub async fn chain_id(&self) -> Result<u64> {
+        Ok(self.call::<U256>("eth_chainId", json!([])).await?.as_u64())
+    }
+
+    pub async fn block_hash(&self, number: u64) -> Result<H256> {
+        let BlockHash { hash } = self
+            .call(
+                "eth_getBlockByNumber",
+                json!([format!("0x{number:x}"), false]),
+            )
+            .await?;
+
+        Ok(hash)
+    }
+
+    pub async fn block_summary(&self, number: u64) -> Result<BlockSummary> {
+        self.call(
+            "eth_getBlockByNumber",
+            json!([format!("0x{number:x}"), false]),
+        )
+        .await
+    }
+
+    /// The blocks of the given numbers in a batch
+    pub async fn block_summaries(
+        &self,
+        numbers: impl IntoIterator<Item = u64>,
+    ) -> Result<Vec<std::result::Result<BlockSummary, String>>> {
+        self.batch_call(
+            "eth_getBlockByNumber",
+            numbers
+                .into_iter()
+                .map(|number| json!([format!("0x{number:x}"), false]))
+                .collect(),
+        )
+        .await
+    }
+
+    pub async fn gas_price(&self) -> Result<U256> {
+        self.call("eth_gasPrice", json!([])).await
+    }
+
+    /// The nonce of the next transaction of an account in the latest block
+    pub async fn transaction_count(&self, address: &H160) -> Result<u64> {
+        Ok(self
+            .call::<U256>("eth_getTransactionCount", json!([address, "latest"]))
+            .await?
+            .as_u64())
+    }
+
+    /// The nonces of the next transactions of accounts in the latest block in
+    /// a batch
+    pub async fn transaction_counts(
+        &self,
+        addresses: &[H160],
+    ) -> Result<Vec<std::result::Result<u64, String>>> {
+        let counts = self
+            .batch_call::<U256>(
+                "eth_getTransactionCount",
+                addresses
+                    .iter()
+                    .map(|address| json!([address, "latest"]))
+                    .collect(),
+            )
+            .await?;
+
+        Ok(counts
+            .into_iter()
+            .map(|count| count.map(|count| count.as_u64()))
+            .collect())
+    }
+
+    /// The balances of accounts in the latest block in a batch
+    pub async fn balances(
+        &self,
+        addresses: &[H160],
+    ) -> Result<Vec<std::result::Result<U256, String>>> {
+        self.batch_call(
+            "eth_getBalance",
+            addresses
+                .iter()
+                .map(|address| json!([address, "latest"]))
+                .collect(),
+        )
+        .await
+    }
+
+    /// Send signed transactions in a batch, returns the hash or the error of
+    /// each one
+    pub async fn send_raw_transactions(
+        &self,
+        txs: &[String],
+    ) -> Result<Vec<std::result::Result<H256, String>>> {
+        self.batch_call(
+            "eth_sendRawTransaction",
+            txs.iter().map(|tx| json!([tx])).collect(),
+        )
+        .await
+    }
+
+    pub async fn peer_count(&self) -> Result<u64> {
+        Ok(self
+            .call::<U256>("net_peerCount", json!([]))
+            .await?
+            .as_u64())
+    }
+
+    /// `eth_syncing` returns `false` when the node is not syncing and an
+    /// object describing the progress otherwise
+    pub async fn syncing(&self) -> Result<bool> {
+        let syncing: Value = self.call("eth_syncing", json!([])).await?;
+
+        Ok(syncing != Value::Bool(false))
+    }
+
+    pub async fn tx_pool_size(&self) -> Result<u64> {
+        let TxPoolStatus { pending, queued } = self.call("txpool_status", json!([])).await?;
+
+        Ok((pending + queued).as_u64())
+    }
+}
+
+/// Check that the Prometheus endpoint of a node responds to scraping
+pub async fn check_metrics(url: impl AsRef<str>) -> Result<()> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(RPC_TIMEOUT))
+        .build()?
+        .get(url.as_ref())
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -0,0 +1,606 @@
+use std::{collections::HashMap, fs::read_to_string, path::Path};
+
+use super::{fake_docker::FakeDocker, TestEnv};
+use crate::{
+    axon_nodes::profile::Profile,
+    constants::{
+        AXON_IMAGE_NAME, AXON_IMAGE_TAG, DEFAULT_AXON_DATA_VOLUME, DEFAULT_AXON_NETWORK_NAME,
+        DEFAULT_SNAPSHOTS_PATH, LABEL_ADDRESS, LABEL_NODE, LABEL_PROFILE, LABEL_ROLE, ROLE_NODE,
+        SNAPSHOT_CONTAINER_NAME,
+    },
+};
+
+fn status(docker: &FakeDocker, name: &str) -> String {
+    docker.container(name).expect("container not found").status
+}
+
+#[tokio::test]
+async fn start_creates_labeled_nodes() {
+    let env = TestEnv::new("start-creates-labeled-nodes");
+    env.start_nodes(2).await;
+
+    for index in 1..=2 {
+        let container = env.docker.container(&format!("axon{index}")).unwrap();
+        assert_eq!(container.status, "running");
+        assert_eq!(
+            container.image,
+            format!("{AXON_IMAGE_NAME}:{AXON_IMAGE_TAG}")
+        );
+        assert_eq!(container.labels[LABEL_PROFILE], env.profile);
+        assert_eq!(container.labels[LABEL_ROLE], ROLE_NODE);
+        assert_eq!(container.labels[LABEL_NODE], index.to_string());
+        assert!(container.networks.contains(DEFAULT_AXON_NETWORK_NAME));
+    }
+
+    let network = env
+        .docker
+        .network_labels(DEFAULT_AXON_NETWORK_NAME)
+        .unwrap();
+    assert_eq!(network[LABEL_PROFILE], env.profile);
+    let volume = env.docker.volume_labels(DEFAULT_AXON_DATA_VOLUME).unwrap();
+    assert_eq!(volume[LABEL_PROFILE], env.profile);
+
+    let profile = Profile::load(&env.profile).unwrap().unwrap();
+    assert_eq!(profile.ports.len(), 2);
+    assert_eq!(profile.path, env.path(""));
+}
+
+#[tokio::test]
+async fn start_keeps_existing_containers() {
+    let env = TestEnv::new("start-keeps-existing-containers");
+    env.start_nodes(1).await;
+    let id = env.docker.container("axon1").unwrap().id;
+
+    env.axon(&env.target(&["stop"])).await.unwrap();
+    env.start_nodes(1).await;
+
+    let container = env.docker.container("axon1").unwrap();
+    assert_eq!(container.id, id);
+    assert_eq!(container.status, "running");
+    assert_eq!(env.docker.container_names(), ["axon1"]);
+}
+
+#[tokio::test]
+async fn start_sets_ulimits_in_the_host_config() {
+    let env = TestEnv::new("start-sets-ulimits-in-the-host-config");
+    env.axon(&["keygen", "-n", "1", "-p", &env.path("key_pairs.json")])
+        .await
+        .unwrap();
+    env.axon(&[
+        "config-gen",
+        "-p",
+        &env.path("nodes"),
+        "-k",
+        &env.path("key_pairs.json"),
+        "--profile",
+        &env.profile,
+    ])
+    .await
+    .unwrap();
+    let root = env.path("");
+
+    env.axon(&env.target(&[
+        "start",
+        "-n",
+        "1",
+        "-P",
+        &root,
+        "--no-wait",
+        "--ulimit",
+        "nofile=65535",
+    ]))
+    .await
+    .unwrap();
+
+    let container = env.docker.container("axon1").unwrap();
+    assert_eq!(container.ulimits, [("nofile".to_string(), 65535)]);
+    assert_eq!(container.cmd[0], "./axon");
+}
+
+#[tokio::test]
+async fn process_backend_rejects_resource_limits() {
+    let env = TestEnv::new("process-backend-rejects-resource-limits");
+    env.axon(&["keygen", "-n", "1", "-p", &env.path("key_pairs.json")])
+        .await
+        .unwrap();
+    env.axon(&[
+        "config-gen",
+        "-p",
+        &env.path("nodes"),
+        "-k",
+        &env.path("key_pairs.json"),
+        "--profile",
+        &env.profile,
+    ])
+    .await
+    .unwrap();
+    let root = env.path("");
+
+    let result = env
+        .axon(&env.target(&[
+            "start",
+            "-n",
+            "1",
+            "-P",
+            &root,
+            "--no-wait",
+            "--backend",
+            "process",
+            "--binary",
+            "/nonexistent/axon",
+            "--memory",
+            "1g",
+        ]))
+        .await;
+
+    assert!(result.is_err());
+    assert!(env.docker.container_names().is_empty());
+}
+
+#[tokio::test]
+async fn start_keeps_the_ports_of_other_nodes() {
+    let env = TestEnv::new("start-keeps-the-ports-of-other-nodes");
+    env.start_nodes(2).await;
+    let ports = Profile::load(&env.profile).unwrap().unwrap().ports;
+
+    let root = env.path("");
+    env.axon(&env.target(&["start", "-n", "1", "-P", &root, "--no-wait"]))
+        .await
+        .unwrap();
+
+    assert_eq!(Profile::load(&env.profile).unwrap().unwrap().ports, ports);
+}
+
+#[tokio::test]
+async fn start_recreates_nodes_of_another_image() {
+    let env = TestEnv::new("start-recreates-nodes-of-another-image");
+    env.start_nodes(1).await;
+    let id = env.docker.container("axon1").unwrap().id;
+    let root = env.path("");
+    let start = env.target(&[
+        "start",
+        "-n",
+        "1",
+        "-P",
+        &root,
+        "--no-wait",
+        "--image",
+        "other/axon",
+        "--tag",
+        "v2",
+    ]);
+
+    // a running node is never replaced by start
+    assert!(env.axon(&start).await.is_err());
+    let profile = Profile::load(&env.profile).unwrap().unwrap();
+    assert_eq!(profile.image, AXON_IMAGE_NAME);
+
+    env.axon(&env.target(&["stop"])).await.unwrap();
+    env.axon(&start).await.unwrap();
+
+    let container = env.docker.container("axon1").unwrap();
+    assert_ne!(container.id, id);
+    assert_eq!(container.image, "other/axon:v2");
+    assert_eq!(container.status, "running");
+    let profile = Profile::load(&env.profile).unwrap().unwrap();
+    assert_eq!(
+        (profile.image.as_str(), profile.tag.as_str()),
+        ("other/axon", "v2")
+    );
+}
+
+#[tokio::test]
+async fn start_refuses_containers_of_other_profiles() {
+    let env = TestEnv::new("start-refuses-containers-of-other-profiles");
+    let labels = HashMap::from([(LABEL_PROFILE.to_string(), "other".to_string())]);
+    env.docker
+        .add_container("axon1", "other/axon", labels.clone(), "exited");
+
+    assert!(env.try_start_nodes(1).await.is_err());
+
+    let container = env.docker.container("axon1").unwrap();
+    assert_eq!(container.status, "exited");
+    assert_eq!(container.labels, labels);
+}
+
+#[tokio::test]
+async fn stop_and_rm_nodes() {
+    let env = TestEnv::new("stop-and-rm-nodes");
+    env.start_nodes(2).await;
+    env.axon(&env.target(&["ps"])).await.unwrap();
+
+    // running containers are kept without "--force"
+    env.axon(&env.target(&["rm"])).await.unwrap();
+    assert_eq!(env.docker.container_names(), ["axon1", "axon2"]);
+
+    env.axon(&env.target(&["stop"])).await.unwrap();
+    assert_eq!(status(&env.docker, "axon1"), "exited");
+    assert_eq!(status(&env.docker, "axon2"), "exited");
+
+    env.axon(&env.target(&["rm"])).await.unwrap();
+    assert!(env.docker.container_names().is_empty());
+
+    // nothing is left to operate on
+    env.axon(&env.target(&["stop"])).await.unwrap();
+    env.axon(&env.target(&["ps"])).await.unwrap();
+}
+
+#[tokio::test]
+async fn rm_force_removes_running_nodes() {
+    let env = TestEnv::new("rm-force-removes-running-nodes");
+    env.start_nodes(2).await;
+
+    env.axon(&env.target(&["rm", "--force"])).await.unwrap();
+
+    assert!(env.docker.container_names().is_empty());
+}
+
+#[tokio::test]
+async fn clean_removes_the_data_volume() {
+    let env = TestEnv::new("clean-removes-the-data-volume");
+    env.start_nodes(1).await;
+    env.axon(&env.target(&["rm", "-f"])).await.unwrap();
+
+    env.axon(&env.target(&["clean"])).await.unwrap();
+    assert!(env.docker.volume_labels(DEFAULT_AXON_DATA_VOLUME).is_none());
+
+    // a missing volume is reported rather than failing
+    env.axon(&env.target(&["clean"])).await.unwrap();
+}
+
+#[tokio::test]
+async fn clean_keeps_the_volume_of_another_profile() {
+    let env = TestEnv::new("clean-keeps-the-volume-of-another-profile");
+    env.start_nodes(1).await;
+    env.axon(&env.target(&["rm", "-f"])).await.unwrap();
+
+    env.axon(&["clean", "--profile", "another", "-d", &env.uri])
+        .await
+        .unwrap_err();
+    assert!(env.docker.volume_labels(DEFAULT_AXON_DATA_VOLUME).is_some());
+}
+
+#[tokio::test]
+async fn logs_exports_lines_merged_by_time() {
+    let env = TestEnv::new("logs-exports-lines-merged-by-time");
+    env.start_nodes(2).await;
+    env.docker
+        .add_log("axon2", "2100-01-01T00:00:01Z", "block 1 committed");
+    env.docker
+        .add_log("axon1", "2100-01-01T00:00:02Z", "block 2 committed");
+    let output = env.path("axon.log");
+
+    env.axon(&env.target(&["logs", "-o", &output, "-g", "committed"]))
+        .await
+        .unwrap();
+
+    let lines = read_to_string(&output).unwrap();
+    let lines = lines.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with("axon2 | block 1 committed"));
+    assert!(lines[1].ends_with("axon1 | block 2 committed"));
+
+    env.axon(&env.target(&["logs", "-i", "1", "-s", "10m"]))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn upgrade_rolls_back_nodes_failing_the_readiness_check() {
+    let env = TestEnv::new("upgrade-rolls-back-nodes");
+    env.start_nodes(1).await;
+    // the rollback goes by the image of the container, not the profile
+    let mut profile = Profile::load(&env.profile).unwrap().unwrap();
+    profile.tag = "edited".to_string();
+    profile.save().unwrap();
+
+    let result = env
+        .axon(&env.target(&["upgrade", "--tag", "next", "-t", "1"]))
+        .await;
+
+    assert!(result.is_err());
+    assert!(env.docker.has_image(&format!("{AXON_IMAGE_NAME}:next")));
+    let container = env.docker.container("axon1").unwrap();
+    assert_eq!(
+        container.image,
+        format!("{AXON_IMAGE_NAME}:{AXON_IMAGE_TAG}")
+    );
+    assert_eq!(container.status, "running");
+    let profile = Profile::load(&env.profile).unwrap().unwrap();
+    assert_eq!(profile.tag, "edited");
+}
+
+#[tokio::test]
+async fn exec_runs_commands_in_running_nodes() {
+    let env = TestEnv::new("exec-runs-commands-in-running-nodes");
+    env.start_nodes(2).await;
+    let exec = |index| {
+        [
+            "exec",
+            "-i",
+            index,
+            "--profile",
+            env.profile.as_str(),
+            "-d",
+            env.uri.as_str(),
+            "--",
+            "echo",
+            "hello",
+        ]
+    };
+
+    env.axon(&exec("2")).await.unwrap();
+    assert_eq!(env.docker.execs(), [("axon2".to_string(), vec![
+        "echo".to_string(),
+        "hello".to_string()
+    ])]);
+
+    // a failing command fails the exec
+    env.docker.set_exec_exit_code(1);
+    assert!(env.axon(&exec("2")).await.is_err());
+    env.docker.set_exec_exit_code(0);
+
+    env.axon(&env.target(&["stop"])).await.unwrap();
+    assert!(env.axon(&exec("1")).await.is_err());
+    assert!(env.axon(&exec("3")).await.is_err());
+}
+
+#[tokio::test]
+async fn net_check_probes_every_pair_of_nodes() {
+    let env = TestEnv::new("net-check-probes-every-pair-of-nodes");
+    env.start_nodes(3).await;
+
+    env.axon(&env.target(&["net", "check"])).await.unwrap();
+    assert_eq!(env.docker.execs().len(), 6);
+
+    env.docker.set_exec_exit_code(1);
+    assert!(env.axon(&env.target(&["net", "check"])).await.is_err());
+
+    // images without nc or bash can't be probed
+    env.docker.set_exec_exit_code(127);
+    assert!(env.axon(&env.target(&["net", "check"])).await.is_err());
+}
+
+#[tokio::test]
+async fn hosts_place_nodes_on_other_daemons() {
+    let env = TestEnv::new("hosts-place-nodes-on-other-daemons");
+    let remote_uri = format!("{}-remote", env.uri);
+    let remote = FakeDocker::register(&remote_uri);
+
+    env.axon(&[
+        "host",
+        "add",
+        "-i",
+        "2",
+        "-u",
+        &remote_uri,
+        "-a",
+        "10.0.0.2",
+        "--profile",
+        &env.profile,
+    ])
+    .await
+    .unwrap();
+    // the bridge address of the default daemon is unreachable from the host
+    assert!(env.try_start_nodes(2).await.is_err());
+
+    env.axon(&[
+        "host",
+        "add",
+        "-i",
+        "1",
+        "-u",
+        &env.uri,
+        "-a",
+        "10.0.0.1",
+        "--profile",
+        &env.profile,
+    ])
+    .await
+    .unwrap();
+    env.axon(&["host", "ls", "--profile", &env.profile])
+        .await
+        .unwrap();
+    env.start_nodes(2).await;
+
+    assert_eq!(env.docker.container_names(), ["axon1"]);
+    assert_eq!(remote.container_names(), ["axon2"]);
+    let container = remote.container("axon2").unwrap();
+    assert_eq!(container.labels[LABEL_ADDRESS], "10.0.0.2");
+    assert!(remote.network_labels(DEFAULT_AXON_NETWORK_NAME).is_some());
+
+    // commands reach each node on its own daemon
+    let mut exec = env.target(&["exec", "-i", "2"]);
+    exec.extend(["--", "true"]);
+    env.axon(&exec).await.unwrap();
+    assert_eq!(remote.execs().len(), 1);
+    assert!(env.docker.execs().is_empty());
+    env.axon(&env.target(&["chaos", "pause", "-i", "2"]))
+        .await
+        .unwrap();
+    assert_eq!(status(&remote, "axon2"), "paused");
+    env.axon(&env.target(&["chaos", "heal"])).await.unwrap();
+    assert_eq!(status(&remote, "axon2"), "running");
+
+    env.axon(&env.target(&["stop"])).await.unwrap();
+    assert_eq!(status(&env.docker, "axon1"), "exited");
+    assert_eq!(status(&remote, "axon2"), "exited");
+
+    env.axon(&["host", "rm", "-i", "2", "--profile", &env.profile])
+        .await
+        .unwrap();
+    let profile = Profile::load(&env.profile).unwrap().unwrap();
+    assert_eq!(profile.hosts.len(), 1);
+}
+
+#[tokio::test]
+async fn keys_shows_generated_key_pairs() {
+    let env = TestEnv::new("keys-shows-generated-key-pairs");
+    let key_pairs = env.path("key_pairs.json");
+
+    env.axon(&["keygen", "-n", "2", "-p", &key_pairs])
+        .await
+        .unwrap();
+    env.axon(&["keys", "-p", &key_pairs]).await.unwrap();
+
+    assert!(env
+        .axon(&["keys", "-p", &env.path("missing.json")])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn image_build_tags_the_built_image() {
+    let env = TestEnv::new("image-build-tags-the-built-image");
+    std::fs::write(env.path("Dockerfile"), "FROM scratch\n").unwrap();
+    let src = env.path("");
+
+    env.axon(&["image", "build", "-s", &src, "-t", "test", "-d", &env.uri])
+        .await
+        .unwrap();
+    assert!(env.docker.has_image("axon:test"));
+
+    let result = env
+        .axon(&[
+            "image", "build", "-s", &src, "-f", "Missing", "-d", &env.uri,
+        ])
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn chaos_latency_fails_with_the_exit_code_of_tc() {
+    let env = TestEnv::new("chaos-latency-fails-with-the-exit-code-of-tc");
+    env.start_nodes(1).await;
+    env.docker.set_exec_exit_code(2);
+
+    env.axon(&env.target(&["chaos", "latency", "-i", "1", "--delay", "100ms"]))
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn chaos_faults_are_healed_in_reverse() {
+    let env = TestEnv::new("chaos-faults-are-healed-in-reverse");
+    env.start_nodes(2).await;
+
+    env.axon(&env.target(&["chaos", "pause", "-i", "1"]))
+        .await
+        .unwrap();
+    env.axon(&env.target(&["chaos", "latency", "-i", "2", "--delay", "100ms"]))
+        .await
+        .unwrap();
+    env.axon(&env.target(&["chaos", "partition", "-i", "2"]))
+        .await
+        .unwrap();
+    env.axon(&env.target(&["chaos", "kill", "-c", "1"]))
+        .await
+        .unwrap();
+
+    assert_eq!(status(&env.docker, "axon1"), "paused");
+    let axon2 = env.docker.container("axon2").unwrap();
+    assert_eq!(axon2.status, "exited");
+    assert!(!axon2.networks.contains(DEFAULT_AXON_NETWORK_NAME));
+
+    // no target selected
+    assert!(env
+        .axon(&env.target(&["chaos", "pause", "-i", "3"]))
+        .await
+        .is_err());
+
+    env.axon(&env.target(&["chaos", "heal"])).await.unwrap();
+    env.axon(&env.target(&["chaos", "timeline"])).await.unwrap();
+
+    assert_eq!(status(&env.docker, "axon1"), "running");
+    let axon2 = env.docker.container("axon2").unwrap();
+    assert_eq!(axon2.status, "running");
+    assert!(axon2.networks.contains(DEFAULT_AXON_NETWORK_NAME));
+    let commands = env
+        .docker
+        .execs()
+        .into_iter()
+        .map(|(_, cmd)| cmd.join(" "))
+        .collect::<Vec<_>>();
+    assert_eq!(commands, [
+        "tc qdisc replace dev eth0 root netem delay 100ms",
+        "tc qdisc del dev eth0 root",
+    ]);
+
+    env.axon(&env.target(&["chaos", "restart", "-i", "1,2"]))
+        .await
+        .unwrap();
+    assert_eq!(status(&env.docker, "axon1"), "running");
+}
+
+#[tokio::test]
+async fn snapshots_archive_with_nodes_stopped() {
+    let env = TestEnv::new("snapshots-archive-with-nodes-stopped");
+    env.start_nodes(1).await;
+    let name = env.profile.clone();
+    let metadata = Path::new(*DEFAULT_SNAPSHOTS_PATH).join(format!("{name}.json"));
+
+    env.axon(&env.target(&["snapshot", "create", &name]))
+        .await
+        .unwrap();
+
+    assert!(metadata.exists());
+    assert_eq!(env.docker.container_names(), ["axon1"]);
+    assert_eq!(status(&env.docker, "axon1"), "running");
+    assert!(env.docker.has_image("busybox"));
+
+    // names are unique
+    assert!(env
+        .axon(&env.target(&["snapshot", "create", &name]))
+        .await
+        .is_err());
+    env.axon(&["snapshot", "list"]).await.unwrap();
+
+    // the fake archiver writes no archive
+    let restore = env.target(&["snapshot", "restore", &name, "-t", "1"]);
+    env.axon(&restore).await.unwrap_err();
+    std::fs::write(
+        Path::new(*DEFAULT_SNAPSHOTS_PATH).join(format!("{name}.tar.gz")),
+        "",
+    )
+    .unwrap();
+
+    env.axon(&restore).await.unwrap();
+    assert!(env.docker.container(SNAPSHOT_CONTAINER_NAME).is_none());
+    assert_eq!(status(&env.docker, "axon1"), "running");
+
+    // the nodes are restarted if the archive can't be restored
+    env.docker.set_exit_code(1);
+    env.axon(&restore).await.unwrap_err();
+    assert_eq!(status(&env.docker, "axon1"), "running");
+}
+
+#[tokio::test]
+async fn failed_snapshots_are_discarded() {
+    let env = TestEnv::new("failed-snapshots-are-discarded");
+    env.start_nodes(1).await;
+    let name = env.profile.clone();
+    env.docker.set_exit_code(1);
+
+    let result = env.axon(&env.target(&["snapshot", "create", &name])).await;
+
+    assert!(result.is_err());
+    assert!(!Path::new(*DEFAULT_SNAPSHOTS_PATH)
+        .join(format!("{name}.json"))
+        .exists());
+    assert!(env.docker.container(SNAPSHOT_CONTAINER_NAME).is_none());
+    assert_eq!(status(&env.docker, "axon1"), "running");
+}
+
+#[tokio::test]
+async fn snapshot_names_are_validated() {
+    let env = TestEnv::new("snapshot-names-are-validated");
+    env.start_nodes(1).await;
+
+    for name in ["", "a b", "a;rm -rf /", "../escape", "a/b", ".hidden"] {
+        let result = env.axon(&env.target(&["snapshot", "create", name])).await;
+
+        assert!(result.is_err(), "{name:?} was accepted");
+        assert!(env.docker.container(SNAPSHOT_CONTAINER_NAME).is_none());
+        assert_eq!(status(&env.docker, "axon1"), "running");
+    }
+}
@@ -0,0 +1,367 @@
+use std::fs::{create_dir_all, remove_file};
+
+use serde_json::{json, Value};
+
+use super::TestEnv;
+use crate::{
+    benchmark::report::{LatencyStats, RunReport},
+    constants::{
+        BENCHMARK_IMAGE_NAME, BENCHMARK_IMAGE_TAG, DEFAULT_AXON_NETWORK_NAME, ROLE_BENCHMARK,
+    },
+    docker::profile_labels,
+    utils::{from_json_file, to_json_file},
+};
+
+/// Save a run of the native engine with the TPS and the p99 of the inclusion
+/// latency
+fn save_run(id: &str, tps: f64, inclusion_p99_ms: f64) {
+    RunReport {
+        id: id.to_string(),
+        tps,
+        inclusion_latency: LatencyStats {
+            p99_ms: inclusion_p99_ms,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+    .save()
+    .unwrap();
+}
+
+#[tokio::test]
+async fn benchmark_joins_the_network_of_nodes() {
+    let env = TestEnv::new("benchmark-joins-the-network-of-nodes");
+    env.start_nodes(1).await;
+    let path = env.path("benchmark");
+
+    env.benchmark(&env.target(&["start", "-p", &path]))
+        .await
+        .unwrap();
+    env.benchmark(&env.target(&["ps"])).await.unwrap();
+
+    let container = env.docker.container("benchmark").unwrap();
+    assert_eq!(container.status, "running");
+    assert_eq!(
+        container.image,
+        format!("{BENCHMARK_IMAGE_NAME}:{BENCHMARK_IMAGE_TAG}")
+    );
+    assert!(container.networks.contains(DEFAULT_AXON_NETWORK_NAME));
+    assert!(container
+        .cmd
+        .contains(&"--http_endpoint=http://axon1:8000".to_string()));
+
+    // the benchmark of another profile is left alone
+    env.benchmark(&["stop", "--profile", "other", "-d", &env.uri])
+        .await
+        .unwrap();
+    assert_eq!(env.docker.container("benchmark").unwrap().status, "running");
+
+    // running containers are kept without "--force"
+    env.benchmark(&env.target(&["rm"])).await.unwrap();
+    assert!(env.docker.container("benchmark").is_some());
+
+    env.benchmark(&env.target(&["stop"])).await.unwrap();
+    assert_eq!(env.docker.container("benchmark").unwrap().status, "exited");
+
+    env.benchmark(&env.target(&["rm"])).await.unwrap();
+    assert!(env.docker.container("benchmark").is_none());
+}
+
+#[tokio::test]
+async fn benchmark_start_reuses_the_container() {
+    let env = TestEnv::new("benchmark-start-reuses-the-container");
+    env.start_nodes(1).await;
+    let path = env.path("benchmark");
+    let start = env.target(&["start", "-p", &path]);
+
+    env.benchmark(&start).await.unwrap();
+    let id = env.docker.container("benchmark").unwrap().id;
+
+    // a running benchmark is kept
+    env.benchmark(&start).await.unwrap();
+    let container = env.docker.container("benchmark").unwrap();
+    assert_eq!(
+        (container.id, container.status.as_str()),
+        (id.clone(), "running")
+    );
+
+    // a stopped one is started again rather than recreated
+    env.benchmark(&env.target(&["stop"])).await.unwrap();
+    env.benchmark(&start).await.unwrap();
+    let container = env.docker.container("benchmark").unwrap();
+    assert_eq!((container.id, container.status.as_str()), (id, "running"));
+}
+
+#[tokio::test]
+async fn benchmark_start_takes_an_untagged_image_as_latest() {
+    let env = TestEnv::new("benchmark-start-takes-an-untagged-image-as-latest");
+    env.start_nodes(1).await;
+    let path = env.path("benchmark");
+    env.docker.add_container(
+        "benchmark",
+        BENCHMARK_IMAGE_NAME,
+        profile_labels(&env.profile, ROLE_BENCHMARK),
+        "exited",
+    );
+    let id = env.docker.container("benchmark").unwrap().id;
+
+    env.benchmark(&env.target(&["start", "-p", &path]))
+        .await
+        .unwrap();
+
+    let container = env.docker.container("benchmark").unwrap();
+    assert_eq!(container.id, id);
+    assert_eq!(container.image, BENCHMARK_IMAGE_NAME);
+    assert_eq!(container.status, "running");
+}
+
+#[tokio::test]
+async fn benchmark_needs_the_network_of_nodes() {
+    let env = TestEnv::new("benchmark-needs-the-network-of-nodes");
+    let path = env.path("benchmark");
+
+    let result = env.benchmark(&env.target(&["start", "-p", &path])).await;
+
+    assert!(result.is_err());
+    assert!(env.docker.container("benchmark").is_none());
+}
+
+#[tokio::test]
+async fn benchmark_config_is_generated_from_the_devnet() {
+    let env = TestEnv::new("benchmark-config-is-generated-from-the-devnet");
+    env.start_nodes(1).await;
+    let path = env.path("benchmark");
+
+    env.benchmark(&env.target(&["start", "-p", &path, "-b", "50"]))
+        .await
+        .unwrap();
+
+    let config: Value = from_json_file(env.path.join("benchmark/config.json")).unwrap();
+    assert_eq!(config["chain_id"], 2022);
+    assert_eq!(
+        config["mnemonic"],
+        "test test test test test test test test test test test junk"
+    );
+    assert_eq!(config["http_endpoint"], "http://axon1:8000");
+    assert_eq!(config["batch_size"], 50);
+}
+
+#[tokio::test]
+async fn benchmark_config_is_patched_and_validated() {
+    let env = TestEnv::new("benchmark-config-is-patched-and-validated");
+    env.start_nodes(1).await;
+    let path = env.path("benchmark");
+    let config_path = env.path.join("benchmark/config.json");
+    create_dir_all(&path).unwrap();
+    to_json_file(
+        &json!({
+            "chain_id": 1,
+            "mnemonic": "junk",
+            "batch_size": 7,
+            "thread_num": 3,
+            "benchmark_time": 1000,
+            "benchmark_cases": ["./benchmark"],
+        }),
+        &config_path,
+    )
+    .unwrap();
+
+    env.benchmark(&env.target(&["start", "-p", &path]))
+        .await
+        .unwrap();
+
+    let mut config: Value = from_json_file(&config_path).unwrap();
+    assert_eq!(config["chain_id"], 2022);
+    assert_eq!(config["batch_size"], 7);
+
+    // a broken config is refused before the container starts
+    env.benchmark(&env.target(&["rm", "-f"])).await.unwrap();
+    config["batch_size"] = json!(0);
+    to_json_file(&config, &config_path).unwrap();
+
+    let result = env
+        .benchmark(&env.target(&["start", "-p", &path, "--keep-config"]))
+        .await;
+
+    assert!(result.is_err());
+    assert!(env.docker.container("benchmark").is_none());
+
+    // nor is a patched one written
+    config["chain_id"] = json!(1);
+    to_json_file(&config, &config_path).unwrap();
+
+    let result = env.benchmark(&env.target(&["start", "-p", &path])).await;
+
+    assert!(result.is_err());
+    let config: Value = from_json_file(&config_path).unwrap();
+    assert_eq!(config["chain_id"], 1);
+}
+
+#[tokio::test]
+async fn benchmark_kept_config_needs_no_devnet() {
+    let env = TestEnv::new("benchmark-kept-config-needs-no-devnet");
+    env.start_nodes(1).await;
+    let path = env.path("benchmark");
+    create_dir_all(&path).unwrap();
+    to_json_file(
+        &json!({
+            "http_endpoint": "http://other:8000",
+            "chain_id": 5,
+            "mnemonic": "test test test test test test test test test test test junk",
+            "batch_size": 7,
+            "thread_num": 3,
+            "benchmark_time": 1000,
+            "benchmark_cases": ["./benchmark"],
+        }),
+        env.path.join("benchmark/config.json"),
+    )
+    .unwrap();
+    remove_file(env.path.join("nodes/genesis.json")).unwrap();
+
+    env.benchmark(&env.target(&["start", "-p", &path, "--keep-config"]))
+        .await
+        .unwrap();
+
+    assert_eq!(env.docker.container("benchmark").unwrap().status, "running");
+    let config: Value = from_json_file(env.path.join("benchmark/config.json")).unwrap();
+    assert_eq!(config["chain_id"], 5);
+}
+
+#[tokio::test]
+async fn benchmark_container_takes_a_single_endpoint() {
+    let env = TestEnv::new("benchmark-container-takes-a-single-endpoint");
+    env.start_nodes(2).await;
+    let path = env.path("benchmark");
+
+    let result = env
+        .benchmark(&env.target(&[
+            "start",
+            "-p",
+            &path,
+            "-H",
+            "http://axon1:8000,http://axon2:8000",
+        ]))
+        .await;
+
+    assert!(result.is_err());
+    assert!(env.docker.container("benchmark").is_none());
+}
+
+#[tokio::test]
+async fn benchmark_follow_fails_with_the_container() {
+    let env = TestEnv::new("benchmark-follow-fails-with-the-container");
+    env.start_nodes(1).await;
+    let path = env.path("benchmark");
+    env.docker.set_exit_code(1);
+
+    let result = env
+        .benchmark(&env.target(&["start", "-p", &path, "--follow"]))
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(env.docker.container("benchmark").unwrap().status, "exited");
+}
+
+#[tokio::test]
+async fn benchmark_follow_needs_the_container_engine() {
+    let env = TestEnv::new("benchmark-follow-needs-the-container-engine");
+    env.start_nodes(1).await;
+
+    let result = env
+        .benchmark(&env.target(&["start", "--engine", "native", "--follow"]))
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn benchmark_logs_follow_the_container_to_its_exit() {
+    let env = TestEnv::new("benchmark-logs-follow-the-container-to-its-exit");
+    env.start_nodes(1).await;
+    let path = env.path("benchmark");
+
+    env.benchmark(&env.target(&["start", "-p", &path]))
+        .await
+        .unwrap();
+    env.docker
+        .add_log("benchmark", "2100-01-01T00:01:04Z", "TPS: 111.36");
+
+    env.benchmark(&["logs", "-d", &env.uri]).await.unwrap();
+    assert_eq!(env.docker.container("benchmark").unwrap().status, "running");
+
+    env.benchmark(&["logs", "-f", "-d", &env.uri])
+        .await
+        .unwrap();
+    assert_eq!(env.docker.container("benchmark").unwrap().status, "exited");
+}
+
+#[tokio::test]
+async fn benchmark_compare_fails_on_regressions() {
+    let env = TestEnv::new("benchmark-compare-fails-on-regressions");
+    let [baseline, similar, slower, later] =
+        ["baseline", "similar", "slower", "later"].map(|name| format!("{}-{name}", env.profile));
+    save_run(&baseline, 100.0, 1000.0);
+    save_run(&similar, 95.0, 1100.0);
+    save_run(&slower, 80.0, 1000.0);
+    save_run(&later, 100.0, 1500.0);
+
+    env.benchmark(&["compare", &baseline, &similar])
+        .await
+        .unwrap();
+    assert!(env
+        .benchmark(&["compare", &baseline, &slower])
+        .await
+        .is_err());
+    assert!(env
+        .benchmark(&["compare", &baseline, &later])
+        .await
+        .is_err());
+
+    // the thresholds are configurable
+    env.benchmark(&["compare", &baseline, &slower, "--max-tps-drop", "25"])
+        .await
+        .unwrap();
+    assert!(env
+        .benchmark(&["compare", &baseline, "missing-run"])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn benchmark_verify_needs_a_block_range() {
+    let env = TestEnv::new("benchmark-verify-needs-a-block-range");
+
+    let result = env.benchmark(&["verify", "--profile", &env.profile]).await;
+    assert!(result.is_err());
+
+    let result = env
+        .benchmark(&[
+            "verify",
+            "--from",
+            "5",
+            "--to",
+            "2",
+            "--profile",
+            &env.profile,
+        ])
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn benchmark_accounts_need_a_valid_mnemonic() {
+    let env = TestEnv::new("benchmark-accounts-need-a-valid-mnemonic");
+
+    let result = env
+        .benchmark(&[
+            "accounts",
+            "list",
+            "--mnemonic",
+            "junk junk junk",
+            "--profile",
+            &env.profile,
+        ])
+        .await;
+
+    assert!(result.is_err());
+}
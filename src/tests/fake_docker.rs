@@ -0,0 +1,522 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use docker_api::{
+    errors::{Error, Result},
+    models::{
+        ContainerInspect200Response, ContainerSummary, ImageBuildChunk, ImageInspect, Network,
+    },
+    opts::ContainerCreateOpts,
+};
+use http::StatusCode;
+use serde_json::{json, Value};
+
+use crate::{
+    docker::{normalize_image, DockerApi, Labels},
+    docker_client::DockerClient,
+};
+
+/// Network modes that exist on every daemon
+const BUILTIN_NETWORKS: [&str; 4] = ["default", "bridge", "host", "none"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeContainer {
+    pub id:       String,
+    pub image:    String,
+    pub labels:   Labels,
+    pub cmd:      Vec<String>,
+    /// the ulimits set in the host config
+    pub ulimits:  Vec<(String, u64)>,
+    /// created, running, paused or exited
+    pub status:   String,
+    pub networks: BTreeSet<String>,
+    /// log lines with their RFC 3339 timestamps
+    pub logs:     Vec<(String, String)>,
+}
+
+impl FakeContainer {
+    fn is_running(&self) -> bool {
+        self.status == "running" || self.status == "paused"
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_id:        u64,
+    containers:     BTreeMap<String, FakeContainer>,
+    networks:       BTreeMap<String, Labels>,
+    volumes:        BTreeMap<String, Labels>,
+    images:         BTreeSet<String>,
+    execs:          Vec<(String, Vec<String>)>,
+    exec_output:    String,
+    exec_exit_code: i64,
+    exit_code:      i64,
+}
+
+/// An in-memory Docker daemon, `DockerApi::new` connects to it when the uri is
+/// the one it was registered with
+#[derive(Debug, Clone, Default)]
+pub struct FakeDocker {
+    state: Arc<Mutex<State>>,
+}
+
+fn fault(code: StatusCode, message: impl Into<String>) -> Error {
+    Error::Fault {
+        code,
+        message: message.into(),
+    }
+}
+
+fn not_found(kind: &str, name: &str) -> Error {
+    fault(StatusCode::NOT_FOUND, format!("No such {kind}: {name}"))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(val: Value) -> T {
+    serde_json::from_value(val).expect("invalid fake model")
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+impl FakeDocker {
+    /// Create a daemon which `DockerApi::new` connects to for `uri`
+    pub fn register(uri: &str) -> Self {
+        let docker = Self::default();
+        let client = docker.clone();
+        DockerApi::register_connector(uri, Box::new(move || Box::new(client.clone())));
+
+        docker
+    }
+
+    fn state(&self) -> MutexGuard<State> {
+        self.state.lock().unwrap()
+    }
+
+    fn with_container<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&mut FakeContainer) -> Result<T>,
+    ) -> Result<T> {
+        let mut state = self.state();
+        let container = state
+            .containers
+            .get_mut(name)
+            .ok_or_else(|| not_found("container", name))?;
+
+        f(container)
+    }
+
+    fn set_status(&self, name: &str, from: &[&str], to: &str) -> Result<()> {
+        self.with_container(name, |container| {
+            if !from.is_empty() && !from.contains(&container.status.as_str()) {
+                return Err(fault(
+                    StatusCode::CONFLICT,
+                    format!("Container {name} is {}", container.status),
+                ));
+            }
+
+            container.status = to.to_string();
+            if to == "running" {
+                container.logs.push((now(), format!("{name} started")));
+            }
+
+            Ok(())
+        })
+    }
+
+    pub fn container(&self, name: &str) -> Option<FakeContainer> {
+        self.state().containers.get(name).cloned()
+    }
+
+    pub fn container_names(&self) -> Vec<String> {
+        self.state().containers.keys().cloned().collect()
+    }
+
+    /// Put a container on the daemon as if another tool had created it
+    pub fn add_container(&self, name: &str, image: &str, labels: Labels, status: &str) {
+        let mut state = self.state();
+        state.next_id += 1;
+        let id = format!("{:064x}", state.next_id);
+
+        state.containers.insert(name.to_string(), FakeContainer {
+            id,
+            image: image.to_string(),
+            labels,
+            cmd: Vec::new(),
+            ulimits: Vec::new(),
+            status: status.to_string(),
+            networks: BTreeSet::new(),
+            logs: Vec::new(),
+        });
+    }
+
+    pub fn add_log(&self, name: &str, timestamp: &str, message: &str) {
+        if let Some(container) = self.state().containers.get_mut(name) {
+            container
+                .logs
+                .push((timestamp.to_string(), message.to_string()));
+        }
+    }
+
+    pub fn network_labels(&self, name: &str) -> Option<Labels> {
+        self.state().networks.get(name).cloned()
+    }
+
+    pub fn volume_labels(&self, name: &str) -> Option<Labels> {
+        self.state().volumes.get(name).cloned()
+    }
+
+    pub fn has_image(&self, image: &str) -> bool {
+        self.state().images.contains(&normalize_image(image))
+    }
+
+    /// The commands run by exec, with the containers they ran in
+    pub fn execs(&self) -> Vec<(String, Vec<String>)> {
+        self.state().execs.clone()
+    }
+
+    /// Make every exec print `output`
+    pub fn set_exec_output(&self, output: &str) {
+        self.state().exec_output = output.to_string();
+    }
+
+    /// Make every exec exit with `code`
+    pub fn set_exec_exit_code(&self, code: i64) {
+        self.state().exec_exit_code = code;
+    }
+
+    /// Make every container exit with `code` when waited for
+    pub fn set_exit_code(&self, code: i64) {
+        self.state().exit_code = code;
+    }
+}
+
+#[async_trait]
+impl DockerClient for FakeDocker {
+    async fn inspect_network(&self, name: &str) -> Result<Network> {
+        let state = self.state();
+        let labels = state
+            .networks
+            .get(name)
+            .ok_or_else(|| not_found("network", name))?;
+
+        Ok(from_json(json!({
+            "Name": name,
+            "Id": name,
+            "Labels": labels,
+        })))
+    }
+
+    async fn create_network(&self, name: &str, labels: &Labels) -> Result<String> {
+        let mut state = self.state();
+        if state.networks.contains_key(name) {
+            return Err(fault(
+                StatusCode::CONFLICT,
+                format!("network with name {name} already exists"),
+            ));
+        }
+        state.networks.insert(name.to_string(), labels.clone());
+
+        Ok(name.to_string())
+    }
+
+    async fn connect_network(&self, network: &str, container: &str) -> Result<()> {
+        if !self.state().networks.contains_key(network) {
+            return Err(not_found("network", network));
+        }
+
+        self.with_container(container, |container| {
+            container.networks.insert(network.to_string());
+            Ok(())
+        })
+    }
+
+    async fn disconnect_network(&self, network: &str, container: &str) -> Result<()> {
+        if !self.state().networks.contains_key(network) {
+            return Err(not_found("network", network));
+        }
+
+        self.with_container(container, |container| {
+            container.networks.remove(network);
+            Ok(())
+        })
+    }
+
+    async fn inspect_volume(&self, name: &str) -> Result<()> {
+        if !self.state().volumes.contains_key(name) {
+            return Err(not_found("volume", name));
+        }
+
+        Ok(())
+    }
+
+    async fn create_volume(&self, name: &str, labels: &Labels) -> Result<()> {
+        self.state()
+            .volumes
+            .entry(name.to_string())
+            .or_insert_with(|| labels.clone());
+
+        Ok(())
+    }
+
+    async fn delete_volume(&self, name: &str) -> Result<()> {
+        match self.state().volumes.remove(name) {
+            Some(_) => Ok(()),
+            None => Err(not_found("volume", name)),
+        }
+    }
+
+    async fn list_volumes(&self, labels: &Labels) -> Result<Vec<String>> {
+        Ok(self
+            .state()
+            .volumes
+            .iter()
+            .filter(|(_, volume)| labels.iter().all(|(key, val)| volume.get(key) == Some(val)))
+            .map(|(name, _)| name.clone())
+            .collect())
+    }
+
+    async fn inspect_image(&self, name: &str) -> Result<ImageInspect> {
+        if !self.has_image(name) {
+            return Err(not_found("image", name));
+        }
+
+        Ok(from_json(json!({ "Id": normalize_image(name) })))
+    }
+
+    async fn pull_image(
+        &self,
+        name: &str,
+        tag: &str,
+        on_chunk: &mut (dyn FnMut(ImageBuildChunk) + Send),
+    ) -> Result<()> {
+        let image = format!("{name}:{tag}");
+        self.state().images.insert(image.clone());
+        on_chunk(ImageBuildChunk::Update {
+            stream: format!("Pulled {image}"),
+        });
+
+        Ok(())
+    }
+
+    async fn build_image(
+        &self,
+        src: &Path,
+        dockerfile: &str,
+        image: &str,
+        on_chunk: &mut (dyn FnMut(ImageBuildChunk) + Send),
+    ) -> Result<()> {
+        if !src.join(dockerfile).exists() {
+            return Err(fault(
+                StatusCode::BAD_REQUEST,
+                format!("Cannot locate specified Dockerfile: {dockerfile}"),
+            ));
+        }
+
+        self.state().images.insert(normalize_image(image));
+        on_chunk(ImageBuildChunk::Update {
+            stream: format!("Successfully tagged {image}\n"),
+        });
+
+        Ok(())
+    }
+
+    async fn inspect_container(&self, name: &str) -> Result<ContainerInspect200Response> {
+        let container = self
+            .container(name)
+            .ok_or_else(|| not_found("container", name))?;
+
+        Ok(from_json(json!({
+            "Id": container.id,
+            "Name": format!("/{name}"),
+            "Image": container.image,
+            "State": {
+                "Status": container.status,
+                "Running": container.is_running(),
+                "Paused": container.status == "paused",
+            },
+            "Config": {
+                "Image": container.image,
+                "Labels": container.labels,
+            },
+        })))
+    }
+
+    async fn list_containers(&self, labels: &Labels) -> Result<Vec<ContainerSummary>> {
+        Ok(self
+            .state()
+            .containers
+            .iter()
+            .filter(|(_, container)| {
+                labels
+                    .iter()
+                    .all(|(key, val)| container.labels.get(key) == Some(val))
+            })
+            .map(|(name, container)| {
+                from_json(json!({
+                    "Id": container.id,
+                    "Names": [format!("/{name}")],
+                    "Image": container.image,
+                    "Labels": container.labels,
+                    "State": container.status,
+                }))
+            })
+            .collect())
+    }
+
+    async fn create_container(
+        &self,
+        opts: &ContainerCreateOpts,
+        ulimits: &[(String, u64)],
+    ) -> Result<String> {
+        let name: &str = opts.name().expect("unnamed container");
+        let opts: Value = serde_json::from_str(&opts.serialize()?).expect("invalid create options");
+        let image = opts["Image"].as_str().unwrap_or_default().to_string();
+        let labels: Labels = serde_json::from_value(opts["Labels"].clone()).unwrap_or_default();
+        let cmd: Vec<String> = serde_json::from_value(opts["Cmd"].clone()).unwrap_or_default();
+        let network = opts["HostConfig"]["NetworkMode"]
+            .as_str()
+            .unwrap_or("default")
+            .to_string();
+
+        let mut state = self.state();
+        if state.containers.contains_key(name) {
+            return Err(fault(
+                StatusCode::CONFLICT,
+                format!("The container name \"/{name}\" is already in use"),
+            ));
+        }
+        // the container keeps the image as given, the store has it tagged
+        if !state.images.contains(&normalize_image(&image)) {
+            return Err(not_found("image", &image));
+        }
+        if !BUILTIN_NETWORKS.contains(&network.as_str()) && !state.networks.contains_key(&network) {
+            return Err(not_found("network", &network));
+        }
+
+        state.next_id += 1;
+        let id = format!("{:064x}", state.next_id);
+        state.containers.insert(name.to_string(), FakeContainer {
+            id: id.clone(),
+            image,
+            labels,
+            cmd,
+            ulimits: ulimits.to_vec(),
+            status: "created".to_string(),
+            networks: BTreeSet::from([network]),
+            logs: Vec::new(),
+        });
+
+        Ok(id)
+    }
+
+    async fn start_container(&self, name: &str) -> Result<()> {
+        self.set_status(name, &["created", "running", "exited"], "running")
+    }
+
+    async fn stop_container(&self, name: &str) -> Result<()> {
+        self.set_status(name, &[], "exited")
+    }
+
+    async fn restart_container(&self, name: &str) -> Result<()> {
+        self.set_status(name, &[], "running")
+    }
+
+    async fn kill_container(&self, name: &str) -> Result<()> {
+        self.set_status(name, &["running"], "exited")
+    }
+
+    async fn pause_container(&self, name: &str) -> Result<()> {
+        self.set_status(name, &["running"], "paused")
+    }
+
+    async fn unpause_container(&self, name: &str) -> Result<()> {
+        self.set_status(name, &["paused"], "running")
+    }
+
+    async fn remove_container(&self, name: &str, force: bool) -> Result<()> {
+        let mut state = self.state();
+        let container = state
+            .containers
+            .get(name)
+            .ok_or_else(|| not_found("container", name))?;
+
+        if container.is_running() && !force {
+            return Err(fault(
+                StatusCode::CONFLICT,
+                format!("You cannot remove a running container {name}"),
+            ));
+        }
+        state.containers.remove(name);
+
+        Ok(())
+    }
+
+    async fn wait_container(&self, name: &str) -> Result<i64> {
+        let exit_code = self.state().exit_code;
+        self.set_status(name, &[], "exited")?;
+
+        Ok(exit_code)
+    }
+
+    async fn container_logs(
+        &self,
+        name: &str,
+        lines: Option<usize>,
+        since: Option<DateTime<Utc>>,
+        _follow: bool,
+        timestamps: bool,
+        on_chunk: &mut (dyn FnMut(Vec<u8>) + Send),
+    ) -> Result<()> {
+        let logs = self
+            .container(name)
+            .ok_or_else(|| not_found("container", name))?
+            .logs
+            .into_iter()
+            .filter(|(timestamp, _)| {
+                since.map_or(true, |since| {
+                    DateTime::parse_from_rfc3339(timestamp).map_or(true, |t| t >= since)
+                })
+            })
+            .collect::<Vec<_>>();
+        let skip = lines.map_or(0, |lines| logs.len().saturating_sub(lines));
+
+        for (timestamp, message) in logs.into_iter().skip(skip) {
+            let line = if timestamps {
+                format!("{timestamp} {message}\n")
+            } else {
+                format!("{message}\n")
+            };
+            on_chunk(line.into_bytes());
+        }
+
+        Ok(())
+    }
+
+    async fn exec(&self, name: &str, cmd: &[String], _privileged: bool) -> Result<(i64, Vec<u8>)> {
+        self.with_container(name, |container| match container.status.as_str() {
+            "running" => Ok(()),
+            status => Err(fault(
+                StatusCode::CONFLICT,
+                format!("Container {name} is {status}"),
+            )),
+        })?;
+
+        let mut state = self.state();
+        state.execs.push((name.to_string(), cmd.to_vec()));
+
+        Ok((state.exec_exit_code, state.exec_output.clone().into_bytes()))
+    }
+
+    async fn exec_tty(&self, name: &str, cmd: &[String], _size: Option<(u16, u16)>) -> Result<i64> {
+        let (exit_code, _) = self.exec(name, cmd, false).await?;
+
+        Ok(exit_code)
+    }
+}
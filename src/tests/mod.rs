@@ -0,0 +1,117 @@
+//! End to end tests of the `axon` and `benchmark` subcommands against
+//! in-memory Docker daemons, except `axon shell` which reads the terminal
+
+mod axon;
+mod benchmark;
+pub mod fake_docker;
+
+use std::{env, fs::create_dir_all, path::PathBuf, sync::Once};
+
+use self::fake_docker::FakeDocker;
+use crate::{
+    axon_nodes::AxonNodes, benchmark::Benchmark, constants::HOME_PATH, sub_command::SubCommand,
+    types::Result,
+};
+
+static INIT: Once = Once::new();
+
+/// Keep the profiles, snapshots and timelines written by tests out of the real
+/// home, this has to happen before any default path is built
+fn init() {
+    INIT.call_once(|| {
+        let home = env::temp_dir().join(format!("axon-cli-tests-{}", std::process::id()));
+        create_dir_all(&home).unwrap();
+        env::set_var("HOME", home);
+    });
+}
+
+async fn run(sub_cmd: &mut dyn SubCommand, args: &[&str]) -> Result<()> {
+    let matches = sub_cmd
+        .get_command()
+        .no_binary_name(true)
+        .try_get_matches_from(args)?;
+
+    sub_cmd.exec_command(&matches).await
+}
+
+/// A profile with a Docker daemon and a working path of its own, so that tests
+/// can run in parallel
+pub struct TestEnv {
+    pub docker:  FakeDocker,
+    pub uri:     String,
+    pub profile: String,
+    pub path:    PathBuf,
+}
+
+impl TestEnv {
+    pub fn new(name: &str) -> Self {
+        init();
+
+        let uri = format!("fake://{name}");
+        let path = PathBuf::from(*HOME_PATH).join(name);
+        create_dir_all(&path).unwrap();
+
+        Self {
+            docker: FakeDocker::register(&uri),
+            uri,
+            profile: name.to_string(),
+            path,
+        }
+    }
+
+    /// A path under the working path of the test
+    pub fn path(&self, name: &str) -> String {
+        self.path.join(name).to_str().unwrap().to_string()
+    }
+
+    /// Append the arguments selecting the profile and the daemon of the test
+    pub fn target<'a>(&'a self, args: &[&'a str]) -> Vec<&'a str> {
+        let mut args = args.to_vec();
+        args.extend(["--profile", self.profile.as_str(), "-d", self.uri.as_str()]);
+
+        args
+    }
+
+    pub async fn axon(&self, args: &[&str]) -> Result<()> {
+        run(&mut AxonNodes::default(), args).await
+    }
+
+    pub async fn benchmark(&self, args: &[&str]) -> Result<()> {
+        run(&mut Benchmark::default(), args).await
+    }
+
+    /// Generate key pairs and config files, and start nodes with them
+    pub async fn start_nodes(&self, number: u32) {
+        self.try_start_nodes(number).await.unwrap();
+    }
+
+    /// Like `start_nodes`, but returns the error of any step
+    pub async fn try_start_nodes(&self, number: u32) -> Result<()> {
+        let number = number.to_string();
+        let key_pairs = self.path("key_pairs.json");
+        let root = self.path("");
+
+        self.axon(&["keygen", "-n", &number, "-p", &key_pairs])
+            .await?;
+        self.axon(&[
+            "config-gen",
+            "-p",
+            &self.path("nodes"),
+            "-k",
+            &key_pairs,
+            "--profile",
+            &self.profile,
+        ])
+        .await?;
+        self.axon(&self.target(&[
+            "start",
+            "-n",
+            &number,
+            "-P",
+            &root,
+            "--no-wait",
+            "--auto-ports",
+        ]))
+        .await
+    }
+}
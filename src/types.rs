@@ -8,16 +8,14 @@ pub struct DockerArgs {
     /// uri of docker service
     #[clap(short, long, default_value=get_default_docker_uri())]
     pub docker_uri: String,
-}
 
-#[derive(Args, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
-pub struct RmContainerArgs {
-    /// force the removal of container
-    #[clap(short, long)]
-    pub force: bool,
+    /// the directory of ca.pem, cert.pem and key.pem to connect with TLS
+    #[clap(long)]
+    pub tls_cert_path: Option<String>,
 
-    #[clap(flatten)]
-    pub docker_args: DockerArgs,
+    /// allow connecting to a non-loopback TCP endpoint without TLS
+    #[clap(long)]
+    pub allow_insecure: bool,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -28,5 +26,5 @@ pub struct ContractJson<'a> {
     pub abi: &'a serde_json::value::RawValue,
 }
 
-pub type Error = Box<dyn std::error::Error>;
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, Error>;
@@ -3,8 +3,10 @@ use std::{
     fs::{read_to_string, write, File},
     io::BufReader,
     path::Path,
+    time::Duration,
 };
 
+use axon_protocol::{codec, types::Address};
 use log::info;
 use serde::{de::DeserializeOwned, Serialize};
 
@@ -66,3 +68,76 @@ pub fn read_or_create_plain_template<V: ?Sized + AsRef<str>>(
 
     Ok(result)
 }
+
+// the errors of the protocol are not `Sync`, so they are converted here rather
+// than at every call
+
+/// Decode hex with or without the `0x` prefix
+pub fn hex_decode(src: &str) -> Result<Vec<u8>> {
+    Ok(codec::hex_decode(src).map_err(|err| err.to_string())?)
+}
+
+/// The address of an uncompressed secp256k1 public key
+pub fn pubkey_address(pubkey: impl AsRef<[u8]>) -> Result<Address> {
+    Ok(Address::from_pubkey_bytes(pubkey).map_err(|err| err.to_string())?)
+}
+
+/// Find the string value of a key in TOML, e.g. `key = "value"`, without
+/// parsing the whole document
+pub fn find_toml_value<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        if name.trim() != key {
+            return None;
+        }
+
+        Some(value.trim().trim_matches('"'))
+    })
+}
+
+/// Parse durations like `500ms`, `30s`, `10m`, `2h` or `1d`, plain numbers are
+/// seconds
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let (value, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => s.split_at(index),
+        None => (s, "s"),
+    };
+    let value: u64 = value.parse()?;
+
+    let seconds = match unit {
+        "ms" => return Ok(Duration::from_millis(value)),
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        _ => return Err(format!("Invalid duration {s}, expected e.g. 30s, 10m or 2h").into()),
+    };
+
+    value
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Duration {s} is too long").into())
+}
+
+/// Parse a size in bytes like 512m or 2g, a plain number is in bytes
+pub fn parse_size(s: &str) -> Result<u64> {
+    let s = s.trim().to_lowercase();
+    let (value, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => s.split_at(index),
+        None => (s.as_str(), "b"),
+    };
+    let value: u64 = value.parse()?;
+
+    let multiplier = match unit.trim_end_matches('b') {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        _ => return Err(format!("Invalid size {s}, expected e.g. 512m or 2g").into()),
+    };
+
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("Size {s} is too large").into())
+}